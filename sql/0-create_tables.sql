@@ -8,6 +8,11 @@ DO $$ BEGIN
         EXCEPTION WHEN DUPLICATE_OBJECT THEN RAISE NOTICE '"text_type" exists, skipping...';
 END $$;
 
+DO $$ BEGIN
+    CREATE TYPE text_status AS ENUM('draft', 'in_review', 'changes_requested', 'approved', 'published', 'unpublished', 'archived');
+        EXCEPTION WHEN DUPLICATE_OBJECT THEN RAISE NOTICE '"text_status" exists, skipping...';
+END $$;
+
 DO $$ BEGIN
     CREATE TYPE text_lang AS ENUM('english', 'swedish');
         EXCEPTION WHEN DUPLICATE_OBJECT THEN RAISE NOTICE '"text_type" exists, skipping...';
@@ -26,8 +31,7 @@ CREATE SEQUENCE IF NOT EXISTS articles_id_seq;
 
 CREATE TABLE IF NOT EXISTS articles (
     id serial NOT NULL PRIMARY KEY,
-    is_published boolean NOT NULL DEFAULT false,
-    marked_as_done boolean NOT NULL DEFAULT false,
+    status text_status NOT NULL DEFAULT 'draft',
     title text NOT NULL,
     title_slug TEXT NOT NULL,
     /* Reference to the username of the creator. */
@@ -39,18 +43,74 @@ CREATE TABLE IF NOT EXISTS articles (
     created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     tags text [] NOT NULL DEFAULT ARRAY[]::integer[],
+    -- The body as plain text, rendered when the text is saved, so that search does not index the JSON of the blocks.
+    body_text text NOT NULL DEFAULT '',
     -- Generate a search vector for title and content. It should prioritize Swedish over English.
     search_vec tsvector GENERATED ALWAYS AS (
         setweight(
-            to_tsvector('swedish', title || ' ' || lead_paragraph || ' ' || text_body),
+            to_tsvector('swedish', title || ' ' || lead_paragraph || ' ' || body_text),
             'A'
         ) || setweight(
-            to_tsvector('english', title || ' ' || lead_paragraph || ' ' || text_body),
+            to_tsvector('english', title || ' ' || lead_paragraph || ' ' || body_text),
             'B'
         )
     ) STORED
 );
 
+-- When set, the text is published automatically once this time has passed.
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS publish_at timestamp with time zone DEFAULT NULL;
+
+-- Usernames of the creators who wrote the text together with `author`, in byline order.
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS co_authors text [] NOT NULL DEFAULT ARRAY[]::text[];
+
+-- Replace the old `is_published` and `marked_as_done` flags with `status`.
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS status text_status NOT NULL DEFAULT 'draft';
+DO $$ BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'articles' AND column_name = 'is_published'
+    ) THEN
+        UPDATE articles SET status = CASE
+            WHEN is_published THEN 'published'::text_status
+            WHEN marked_as_done THEN 'in_review'::text_status
+            ELSE 'draft'::text_status
+        END;
+        ALTER TABLE articles DROP COLUMN is_published, DROP COLUMN marked_as_done;
+    END IF;
+END $$;
+
+-- Index `body_text` instead of the JSON of the blocks.
+-- Texts saved before get their JSON as `body_text` here, which `Text::fill_body_text` replaces with their plain text.
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS body_text text NOT NULL DEFAULT '';
+DO $$ BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'articles' AND column_name = 'search_vec' AND generation_expression LIKE '%text_body%'
+    ) THEN
+        UPDATE articles SET body_text = text_body::text;
+        ALTER TABLE articles DROP COLUMN search_vec;
+        ALTER TABLE articles ADD COLUMN search_vec tsvector GENERATED ALWAYS AS (
+            setweight(
+                to_tsvector('swedish', title || ' ' || lead_paragraph || ' ' || body_text),
+                'A'
+            ) || setweight(
+                to_tsvector('english', title || ' ' || lead_paragraph || ' ' || body_text),
+                'B'
+            )
+        ) STORED;
+    END IF;
+END $$;
+
+CREATE TABLE IF NOT EXISTS text_status_transitions (
+    id serial NOT NULL PRIMARY KEY,
+    text_id integer NOT NULL REFERENCES articles (id) ON DELETE CASCADE,
+    from_status text_status NOT NULL,
+    to_status text_status NOT NULL,
+    /* Reference to the username of the creator; NULL if done automatically, e.g. scheduled publishing. */
+    actor text DEFAULT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
 CREATE TABLE IF NOT EXISTS images (
     id uuid NOT NULL PRIMARY KEY,
     author text NOT NULL,
@@ -75,9 +135,128 @@ CREATE TABLE IF NOT EXISTS pages (
     text_body jsonb NOT NULL
 );
 
+-- Trashed rows have `deleted_at` set, and are purged once they have been trashed long enough.
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS deleted_at timestamp with time zone DEFAULT NULL;
+ALTER TABLE images ADD COLUMN IF NOT EXISTS deleted_at timestamp with time zone DEFAULT NULL;
+ALTER TABLE pages ADD COLUMN IF NOT EXISTS deleted_at timestamp with time zone DEFAULT NULL;
+
+-- Used to detect when two people edit the same page at the same time.
+ALTER TABLE pages ADD COLUMN IF NOT EXISTS updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE TABLE IF NOT EXISTS text_revisions (
+    id serial NOT NULL PRIMARY KEY,
+    text_id integer NOT NULL REFERENCES articles (id) ON DELETE CASCADE,
+    /* Reference to the username of the creator who saved the revision. */
+    author text NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    title text NOT NULL,
+    thumbnail uuid DEFAULT NULL,
+    lead_paragraph text NOT NULL,
+    text_body jsonb NOT NULL,
+    text_type text_type NOT NULL,
+    tags text [] NOT NULL DEFAULT ARRAY[]::integer[]
+);
+
+CREATE TABLE IF NOT EXISTS review_comments (
+    id serial NOT NULL PRIMARY KEY,
+    text_id integer NOT NULL REFERENCES articles (id) ON DELETE CASCADE,
+    /* The comment this is a reply to; NULL for the first comment in a thread. */
+    parent_id integer DEFAULT NULL REFERENCES review_comments (id) ON DELETE CASCADE,
+    /* Index of the block in `articles.text_body` the comment is about; NULL for the whole text. */
+    block_index integer DEFAULT NULL,
+    /* Reference to the username of the creator. */
+    author text NOT NULL,
+    body text NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at timestamp with time zone DEFAULT NULL,
+    resolved_by text DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS text_corrections (
+    id serial NOT NULL PRIMARY KEY,
+    text_id integer NOT NULL REFERENCES articles (id) ON DELETE CASCADE,
+    body text NOT NULL,
+    /* Reference to the username of the publisher who added the correction. */
+    author text NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS preview_links (
+    id serial NOT NULL PRIMARY KEY,
+    text_id integer NOT NULL REFERENCES articles (id) ON DELETE CASCADE,
+    /* Reference to the username of the creator who created the link. */
+    created_by text NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at timestamp with time zone NOT NULL,
+    revoked_at timestamp with time zone DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS autosaves (
+    id serial NOT NULL PRIMARY KEY,
+    /* Reference to the username of the creator whose working copy this is. */
+    creator text NOT NULL REFERENCES creators (username) ON DELETE CASCADE,
+    /* The text being edited; NULL for a text that has not been saved yet. */
+    text_id integer DEFAULT NULL REFERENCES articles (id) ON DELETE CASCADE,
+    title text NOT NULL,
+    lead_paragraph text NOT NULL,
+    text_body jsonb NOT NULL,
+    text_type text_type NOT NULL,
+    tags text[] NOT NULL,
+    saved_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- The `updated_at` of the text the working copy was based on, so that recovering it after someone else has saved
+-- the text gives a conflict instead of overwriting their changes; NULL for a text that has not been saved yet.
+ALTER TABLE autosaves ADD COLUMN IF NOT EXISTS base_updated_at timestamp with time zone DEFAULT NULL;
+ALTER TABLE autosaves ADD COLUMN IF NOT EXISTS co_authors text[] NOT NULL DEFAULT ARRAY[]::text[];
+
+CREATE TABLE IF NOT EXISTS poll_votes (
+    id serial NOT NULL PRIMARY KEY,
+    /* The `id` of the `Poll` block that was voted on. */
+    poll_id uuid NOT NULL,
+    /* The index of the chosen option among the options of the poll. */
+    option_index integer NOT NULL,
+    /* Random id from a cookie, so that every browser only votes once per poll. */
+    voter uuid NOT NULL,
+    voted_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+/* Audio files, e.g. podcast episodes. The files are stored in `${DATA_DIR}/audio`. */
+CREATE TABLE IF NOT EXISTS audio (
+    id uuid NOT NULL PRIMARY KEY,
+    /* Reference to the username of the creator who uploaded the file. */
+    uploaded_by text NOT NULL,
+    title text NOT NULL,
+    description text,
+    /* Decides the extension of the file, see `AUDIO_FORMATS`. */
+    mime_type text NOT NULL,
+    duration_secs integer NOT NULL,
+    size_bytes bigint NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at timestamp with time zone DEFAULT NULL
+);
+
+/* Blocks that are edited in one place and shown wherever a `Snippet` block refers to them. */
+CREATE TABLE IF NOT EXISTS snippets (
+    id uuid NOT NULL PRIMARY KEY,
+    name text NOT NULL,
+    text_body jsonb NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
 CREATE INDEX IF NOT EXISTS idx_articles_title ON articles (title);
 CREATE INDEX IF NOT EXISTS idx_articles_tags ON articles USING GIN (tags);
 CREATE INDEX IF NOT EXISTS idx_articles_search ON articles USING GIN (search_vec);
 
+CREATE INDEX IF NOT EXISTS idx_text_revisions_text_id ON text_revisions (text_id);
+CREATE INDEX IF NOT EXISTS idx_text_status_transitions_text_id ON text_status_transitions (text_id);
+CREATE INDEX IF NOT EXISTS idx_review_comments_text_id ON review_comments (text_id);
+CREATE INDEX IF NOT EXISTS idx_text_corrections_text_id ON text_corrections (text_id);
+CREATE INDEX IF NOT EXISTS idx_preview_links_text_id ON preview_links (text_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_poll_votes_poll_id_voter ON poll_votes (poll_id, voter);
+/* One working copy per creator and text, where all unsaved texts count as the same text. */
+CREATE UNIQUE INDEX IF NOT EXISTS idx_autosaves_creator_text_id ON autosaves (creator, COALESCE(text_id, 0));
+
 CREATE INDEX IF NOT EXISTS idx_images_tags ON images USING GIN (tags);
 CREATE INDEX IF NOT EXISTS idx_images_search ON images USING GIN (search_vec);
@@ -1,3 +1,3 @@
 SELECT ARRAY (SELECT tag FROM 
-    (SELECT UNNEST(tags) as tag FROM articles WHERE is_published = true) 
+    (SELECT UNNEST(tags) as tag FROM articles WHERE status = 'published' AND deleted_at IS NULL) AS article_tags
 GROUP BY tag ORDER BY COUNT(*) DESC LIMIT $1);
@@ -10,8 +10,14 @@ SELECT
     articles.created_at,
     updated_at,
     articles.tags,
-    is_published,
-    marked_as_done,
+    status AS "status!: TextStatus",
+    publish_at AS "publish_at: DateTime<Local>",
+    articles.co_authors,
+    ARRAY(
+        SELECT co_creator FROM unnest(articles.co_authors) WITH ORDINALITY AS co_author (username, position)
+        JOIN creators AS co_creator ON co_creator.username = co_author.username
+        ORDER BY co_author.position
+    ) AS "co_creators!: Vec<Creator>",
     creators AS "creator!: Creator",
     images AS "thumbnail?: Image"
 FROM
@@ -19,6 +25,6 @@ FROM
 JOIN creators ON
     articles.author = creators.username
 LEFT JOIN images ON
-    articles.thumbnail = images.id
+    articles.thumbnail = images.id AND images.deleted_at IS NULL
 WHERE
-    $1 && articles.tags AND is_published = true
+    articles.deleted_at IS NULL AND $1 && articles.tags AND status = 'published'
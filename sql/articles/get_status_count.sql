@@ -3,4 +3,4 @@ SELECT
 FROM
     articles
 WHERE
-    marked_as_done = true AND is_published = false
+    status = $1 AND deleted_at IS NULL
@@ -0,0 +1,34 @@
+SELECT
+    articles.id,
+    title,
+    title_slug,
+    articles.author,
+    thumbnail AS "thumbnail_id",
+    lead_paragraph,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    text_type AS "text_type!: TextType",
+    articles.created_at,
+    updated_at,
+    articles.tags,
+    status AS "status!: TextStatus",
+    publish_at AS "publish_at: DateTime<Local>",
+    articles.co_authors,
+    ARRAY(
+        SELECT co_creator FROM unnest(articles.co_authors) WITH ORDINALITY AS co_author (username, position)
+        JOIN creators AS co_creator ON co_creator.username = co_author.username
+        ORDER BY co_author.position
+    ) AS "co_creators!: Vec<Creator>",
+    creators AS "creator!: Creator",
+    images AS "thumbnail?: Image"
+FROM
+    articles
+JOIN creators ON
+    articles.author = creators.username
+LEFT JOIN images ON
+    articles.thumbnail = images.id AND images.deleted_at IS NULL
+WHERE
+    articles.deleted_at IS NULL AND status = 'published' AND text_body @> '[{"type": "Audio"}]'
+ORDER BY
+    COALESCE(publish_at, articles.created_at) DESC
+LIMIT
+    $1
@@ -12,11 +12,12 @@ WITH inserted_article AS (
             created_at,
             updated_at,
             tags,
-            is_published,
-            marked_as_done
+            status,
+            co_authors,
+            body_text
         )
     VALUES
-        (DEFAULT, $1, $2, $3, $4, $5, $6, $7, DEFAULT, DEFAULT, $8, $9, $10) RETURNING *
+        (DEFAULT, $1, $2, $3, $4, $5, $6, $7, DEFAULT, DEFAULT, $8, $9, $10, $11) RETURNING *
 )
 SELECT
     inserted_article.id,
@@ -30,12 +31,18 @@ SELECT
     inserted_article.created_at,
     updated_at,
     inserted_article.tags,
-    is_published,
-    marked_as_done,
+    status AS "status!: TextStatus",
+    publish_at AS "publish_at: DateTime<Local>",
+    inserted_article.co_authors,
+    ARRAY(
+        SELECT co_creator FROM unnest(inserted_article.co_authors) WITH ORDINALITY AS co_author (username, position)
+        JOIN creators AS co_creator ON co_creator.username = co_author.username
+        ORDER BY co_author.position
+    ) AS "co_creators!: Vec<Creator>",
     creators AS "creator!: Creator",
     images AS "thumbnail?: Image"
 FROM inserted_article
 JOIN creators ON
     inserted_article.author = creators.username
 LEFT JOIN images ON
-    inserted_article.thumbnail = images.id
+    inserted_article.thumbnail = images.id AND images.deleted_at IS NULL
@@ -0,0 +1,23 @@
+WITH published AS (
+    UPDATE articles
+    SET
+        status = 'published'
+    FROM
+        articles AS previous
+    WHERE
+        articles.id = previous.id
+        AND articles.status IN ('approved', 'unpublished')
+        AND articles.publish_at <= NOW()
+        AND articles.deleted_at IS NULL
+    RETURNING
+        articles.id,
+        previous.status AS previous_status
+)
+INSERT INTO
+    text_status_transitions (text_id, from_status, to_status, actor)
+SELECT
+    id, previous_status, 'published', NULL
+FROM
+    published
+RETURNING
+    text_id
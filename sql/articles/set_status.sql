@@ -0,0 +1,18 @@
+WITH updated AS (
+    UPDATE articles
+    SET
+        status = $2,
+        publish_at = NULL
+    WHERE
+        id = $1 AND status = $3
+    RETURNING
+        id
+)
+INSERT INTO
+    text_status_transitions (text_id, from_status, to_status, actor)
+SELECT
+    id, $3, $2, $4
+FROM
+    updated
+RETURNING
+    text_id
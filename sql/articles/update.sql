@@ -1,52 +1,43 @@
-UPDATE articles SET
-    title = $1,
-    title_slug = $2,
-    thumbnail = $3,
-    lead_paragraph = $4,
-    text_body = $5,
-    text_type = $6,
-    updated_at = NOW(),
-    tags = $7
-FROM (
-    SELECT
-        articles.id,
-        title,
-        title_slug,
-        articles.author,
-        thumbnail,
-        lead_paragraph,
-        text_body,
-        text_type,
-        articles.created_at,
-        updated_at,
-        articles.tags,
-        is_published,
-        marked_as_done,
-        creators,
-        images
-    FROM
-        articles
-    JOIN creators ON
-        articles.author = creators.username
-    LEFT JOIN images ON
-        articles.thumbnail = images.id
-    WHERE articles.id = $8
-) AS updated_row
-WHERE
-    articles.id = $8
-RETURNING
-    updated_row.id,
-    updated_row.title,
-    updated_row.title_slug,
-    updated_row.author,
-    updated_row.thumbnail AS "thumbnail_id",
-    updated_row.lead_paragraph,
-    updated_row.text_body AS "text_body!: Json<Vec<Block>>",
-    updated_row.text_type AS "text_type!: TextType",
-    updated_row.created_at,
-    updated_row.updated_at,
-    updated_row.tags,
-    updated_row.is_published,
-    updated_row.marked_as_done,
-    updated_row.creators AS "creator!: Creator",
-    updated_row.images AS "thumbnail?: Image"
+WITH updated_article AS (
+    UPDATE articles SET
+        title = $1,
+        title_slug = $2,
+        thumbnail = $3,
+        lead_paragraph = $4,
+        text_body = $5,
+        text_type = $6,
+        updated_at = NOW(),
+        tags = $7,
+        co_authors = $9,
+        body_text = $11
+    WHERE
+        id = $8 AND deleted_at IS NULL AND ($10::timestamptz IS NULL OR updated_at = $10)
+    RETURNING *
+)
+SELECT
+    updated_article.id,
+    title,
+    title_slug,
+    updated_article.author,
+    thumbnail AS "thumbnail_id",
+    lead_paragraph,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    text_type AS "text_type!: TextType",
+    updated_article.created_at,
+    updated_at,
+    updated_article.tags,
+    status AS "status!: TextStatus",
+    publish_at AS "publish_at: DateTime<Local>",
+    updated_article.co_authors,
+    ARRAY(
+        SELECT co_creator FROM unnest(updated_article.co_authors) WITH ORDINALITY AS co_author (username, position)
+        JOIN creators AS co_creator ON co_creator.username = co_author.username
+        ORDER BY co_author.position
+    ) AS "co_creators!: Vec<Creator>",
+    creators AS "creator!: Creator",
+    images AS "thumbnail?: Image"
+FROM updated_article
+JOIN creators ON
+    updated_article.author = creators.username
+LEFT JOIN images ON
+    updated_article.thumbnail = images.id AND images.deleted_at IS NULL
@@ -0,0 +1,15 @@
+SELECT
+    id,
+    uploaded_by,
+    title,
+    description,
+    mime_type,
+    duration_secs,
+    size_bytes,
+    created_at
+FROM
+    audio
+WHERE
+    deleted_at IS NULL
+ORDER BY
+    created_at DESC
@@ -0,0 +1,13 @@
+SELECT
+    id,
+    uploaded_by,
+    title,
+    description,
+    mime_type,
+    duration_secs,
+    size_bytes,
+    created_at
+FROM
+    audio
+WHERE
+    deleted_at IS NULL AND id = ANY($1)
@@ -0,0 +1,22 @@
+INSERT INTO
+    audio (
+        id,
+        uploaded_by,
+        title,
+        description,
+        mime_type,
+        duration_secs,
+        size_bytes,
+        created_at
+    )
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7, DEFAULT)
+RETURNING
+    id,
+    uploaded_by,
+    title,
+    description,
+    mime_type,
+    duration_secs,
+    size_bytes,
+    created_at
@@ -0,0 +1,4 @@
+DELETE FROM
+    autosaves
+WHERE
+    creator = $1 AND text_id IS NOT DISTINCT FROM $2
@@ -0,0 +1,15 @@
+SELECT
+    creator,
+    text_id,
+    title,
+    lead_paragraph,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    text_type AS "text_type!: TextType",
+    tags,
+    co_authors,
+    base_updated_at AS "base_updated_at: DateTime<Local>",
+    saved_at
+FROM
+    autosaves
+WHERE
+    creator = $1 AND text_id IS NOT DISTINCT FROM $2
@@ -0,0 +1,24 @@
+INSERT INTO
+    autosaves (creator, text_id, title, lead_paragraph, text_body, text_type, tags, co_authors, base_updated_at)
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+ON CONFLICT (creator, COALESCE(text_id, 0)) DO UPDATE SET
+    title = EXCLUDED.title,
+    lead_paragraph = EXCLUDED.lead_paragraph,
+    text_body = EXCLUDED.text_body,
+    text_type = EXCLUDED.text_type,
+    tags = EXCLUDED.tags,
+    co_authors = EXCLUDED.co_authors,
+    base_updated_at = EXCLUDED.base_updated_at,
+    saved_at = NOW()
+RETURNING
+    creator,
+    text_id,
+    title,
+    lead_paragraph,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    text_type AS "text_type!: TextType",
+    tags,
+    co_authors,
+    base_updated_at AS "base_updated_at: DateTime<Local>",
+    saved_at
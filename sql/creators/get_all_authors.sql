@@ -6,11 +6,12 @@ SELECT
     joined_at,
     role AS "role!: CreatorRole"
 FROM (
-    SELECT author, COUNT(*) as total_articles
+    SELECT byline.username AS author, COUNT(*) as total_articles
     FROM
-        articles
+        articles,
+        unnest(array_prepend(articles.author, articles.co_authors)) AS byline (username)
     WHERE
-        is_published = true
-    GROUP BY author
-) JOIN creators ON author = creators.username
+        status = 'published' AND deleted_at IS NULL
+    GROUP BY byline.username
+) AS authors JOIN creators ON author = creators.username
 ORDER BY total_articles DESC
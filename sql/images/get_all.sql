@@ -6,3 +6,5 @@ SELECT
     tags
 FROM
     images
+WHERE
+    deleted_at IS NULL
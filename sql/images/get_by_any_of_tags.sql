@@ -7,4 +7,4 @@ SELECT
 FROM
     images
 WHERE
-    $1 && tags
+    deleted_at IS NULL AND $1 && tags
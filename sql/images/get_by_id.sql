@@ -7,4 +7,4 @@ SELECT
 FROM
     images
 WHERE
-    id = $1
+    deleted_at IS NULL AND id = $1
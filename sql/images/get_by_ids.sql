@@ -0,0 +1,10 @@
+SELECT
+    id,
+    author,
+    description,
+    created_at,
+    tags
+FROM
+    images
+WHERE
+    deleted_at IS NULL AND id = ANY($1)
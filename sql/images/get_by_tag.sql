@@ -7,4 +7,4 @@ SELECT
 FROM
     images
 WHERE
-    $1 = ANY(tags)
+    deleted_at IS NULL AND $1 = ANY(tags)
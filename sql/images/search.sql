@@ -8,4 +8,4 @@ FROM
     to_tsquery(FORMAT('%s', ARRAY_TO_STRING(STRING_TO_ARRAY($1, ' '), ' & '))) AS search_query,
     images
 WHERE
-    search_query @@ search_vec
+    deleted_at IS NULL AND search_query @@ search_vec
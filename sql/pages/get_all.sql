@@ -1,6 +1,9 @@
 SELECT
     path,
     title,
-    text_body AS "text_body!: Json<Vec<Block>>"
+    text_body AS "text_body!: Json<Vec<Block>>",
+    updated_at
 FROM
     pages
+WHERE
+    deleted_at IS NULL
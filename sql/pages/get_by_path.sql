@@ -1,8 +1,9 @@
 SELECT
     path,
     title,
-    text_body AS "text_body!: Json<Vec<Block>>"
+    text_body AS "text_body!: Json<Vec<Block>>",
+    updated_at
 FROM
     pages
 WHERE
-    path = $1
+    deleted_at IS NULL AND path = $1
@@ -9,4 +9,5 @@ VALUES
 RETURNING
     path,
     title,
-    text_body AS "text_body!: Json<Vec<Block>>"
+    text_body AS "text_body!: Json<Vec<Block>>",
+    updated_at
@@ -2,10 +2,12 @@ UPDATE pages
 SET
     path = $2,
     title = $3,
-    text_body = $4
+    text_body = $4,
+    updated_at = NOW()
 WHERE
-    path = $1
+    path = $1 AND deleted_at IS NULL AND ($5::timestamptz IS NULL OR updated_at = $5)
 RETURNING
     path,
     title,
-    text_body AS "text_body!: Json<Vec<Block>>"
+    text_body AS "text_body!: Json<Vec<Block>>",
+    updated_at
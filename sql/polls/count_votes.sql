@@ -0,0 +1,9 @@
+SELECT
+    option_index,
+    COUNT(*) AS "votes!"
+FROM
+    poll_votes
+WHERE
+    poll_id = $1
+GROUP BY
+    option_index
@@ -0,0 +1,11 @@
+SELECT
+    poll_id,
+    option_index,
+    COUNT(*) AS "votes!"
+FROM
+    poll_votes
+WHERE
+    poll_id = ANY($1)
+GROUP BY
+    poll_id,
+    option_index
@@ -0,0 +1,29 @@
+SELECT
+    poll AS "poll!: Json<Poll>",
+    title AS "title!",
+    url AS "url!",
+    is_published AS "is_published!",
+    (SELECT COUNT(*) FROM poll_votes WHERE poll_votes.poll_id::text = poll->>'id') AS "votes!"
+FROM (
+    SELECT block AS poll, title, '/t/' || id || '/' || title_slug AS url, status = 'published' AS is_published
+    FROM articles, jsonb_array_elements(text_body) AS block
+    WHERE deleted_at IS NULL AND block->>'type' = 'Poll'
+    UNION ALL
+    SELECT block AS poll, title, '/' || path AS url, TRUE AS is_published
+    FROM pages, jsonb_array_elements(text_body) AS block
+    WHERE deleted_at IS NULL AND block->>'type' = 'Poll'
+    UNION ALL
+    -- Polls in snippets are where the snippets are used.
+    SELECT block AS poll, articles.title, '/t/' || articles.id || '/' || articles.title_slug AS url,
+        articles.status = 'published' AS is_published
+    FROM snippets, jsonb_array_elements(snippets.text_body) AS block, articles
+    WHERE articles.deleted_at IS NULL AND block->>'type' = 'Poll'
+        AND articles.text_body @> jsonb_build_array(jsonb_build_object('type', 'Snippet', 'id', snippets.id::text))
+    UNION ALL
+    SELECT block AS poll, pages.title, '/' || pages.path AS url, TRUE AS is_published
+    FROM snippets, jsonb_array_elements(snippets.text_body) AS block, pages
+    WHERE pages.deleted_at IS NULL AND block->>'type' = 'Poll'
+        AND pages.text_body @> jsonb_build_array(jsonb_build_object('type', 'Snippet', 'id', snippets.id::text))
+) AS polls
+ORDER BY
+    title
@@ -0,0 +1,3 @@
+SELECT EXISTS (
+    SELECT 1 FROM poll_votes WHERE poll_id = $1 AND voter = $2
+) AS "has_voted!"
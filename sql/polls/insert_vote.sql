@@ -0,0 +1,3 @@
+INSERT INTO poll_votes (poll_id, option_index, voter)
+VALUES ($1, $2, $3)
+ON CONFLICT (poll_id, voter) DO NOTHING
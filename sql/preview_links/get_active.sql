@@ -0,0 +1,17 @@
+SELECT
+    preview_links.id,
+    preview_links.text_id,
+    preview_links.created_by,
+    preview_links.created_at,
+    preview_links.expires_at,
+    preview_links.revoked_at AS "revoked_at: DateTime<Local>",
+    articles.title AS text_title,
+    articles.title_slug AS text_title_slug
+FROM
+    preview_links
+JOIN articles ON
+    preview_links.text_id = articles.id
+WHERE
+    preview_links.revoked_at IS NULL AND preview_links.expires_at > NOW()
+ORDER BY
+    preview_links.expires_at ASC
@@ -0,0 +1,19 @@
+WITH inserted_link AS (
+    INSERT INTO
+        preview_links (text_id, created_by, expires_at)
+    VALUES
+        ($1, $2, $3)
+    RETURNING *
+)
+SELECT
+    inserted_link.id,
+    inserted_link.text_id,
+    inserted_link.created_by,
+    inserted_link.created_at,
+    inserted_link.expires_at,
+    inserted_link.revoked_at AS "revoked_at: DateTime<Local>",
+    articles.title AS text_title,
+    articles.title_slug AS text_title_slug
+FROM inserted_link
+JOIN articles ON
+    inserted_link.text_id = articles.id
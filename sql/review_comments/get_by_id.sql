@@ -0,0 +1,14 @@
+SELECT
+    id,
+    text_id,
+    parent_id,
+    block_index,
+    author,
+    body,
+    created_at,
+    resolved_at AS "resolved_at: DateTime<Local>",
+    resolved_by
+FROM
+    review_comments
+WHERE
+    id = $1
@@ -0,0 +1,6 @@
+SELECT
+    COUNT(*) AS "count!: i64"
+FROM
+    review_comments
+WHERE
+    text_id = $1 AND parent_id IS NULL AND resolved_at IS NULL
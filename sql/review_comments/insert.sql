@@ -0,0 +1,20 @@
+INSERT INTO
+    review_comments (
+        text_id,
+        parent_id,
+        block_index,
+        author,
+        body
+    )
+VALUES
+    ($1, $2, $3, $4, $5)
+RETURNING
+    id,
+    text_id,
+    parent_id,
+    block_index,
+    author,
+    body,
+    created_at,
+    resolved_at AS "resolved_at: DateTime<Local>",
+    resolved_by
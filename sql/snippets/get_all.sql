@@ -0,0 +1,10 @@
+SELECT
+    id,
+    name,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    created_at,
+    updated_at
+FROM
+    snippets
+ORDER BY
+    name
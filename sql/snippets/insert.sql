@@ -0,0 +1,14 @@
+INSERT INTO
+    snippets (
+        id,
+        name,
+        text_body
+    )
+VALUES
+    ($1, $2, $3)
+RETURNING
+    id,
+    name,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    created_at,
+    updated_at
@@ -0,0 +1,13 @@
+UPDATE snippets
+SET
+    name = $2,
+    text_body = $3,
+    updated_at = NOW()
+WHERE
+    id = $1 AND ($4::timestamptz IS NULL OR updated_at = $4)
+RETURNING
+    id,
+    name,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    created_at,
+    updated_at
@@ -0,0 +1,17 @@
+SELECT
+    'text' AS "kind!",
+    id::text AS "key!",
+    title AS "title!"
+FROM
+    articles
+WHERE
+    deleted_at IS NULL AND text_body @> $1
+UNION ALL
+SELECT
+    'page',
+    path,
+    title
+FROM
+    pages
+WHERE
+    deleted_at IS NULL AND text_body @> $1
@@ -0,0 +1,16 @@
+SELECT
+    text_corrections.id,
+    text_corrections.text_id,
+    text_corrections.body,
+    text_corrections.author,
+    text_corrections.created_at,
+    articles.title AS text_title,
+    articles.title_slug AS text_title_slug
+FROM
+    text_corrections
+JOIN articles ON
+    text_corrections.text_id = articles.id
+WHERE
+    articles.status = 'published' AND articles.deleted_at IS NULL
+ORDER BY
+    text_corrections.created_at DESC
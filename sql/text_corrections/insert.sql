@@ -0,0 +1,25 @@
+WITH inserted_correction AS (
+    INSERT INTO
+        text_corrections (text_id, body, author)
+    VALUES
+        ($1, $2, $3)
+    RETURNING *
+), bumped_article AS (
+    UPDATE articles SET
+        updated_at = inserted_correction.created_at
+    FROM inserted_correction
+    WHERE
+        articles.id = inserted_correction.text_id
+    RETURNING articles.id, articles.title, articles.title_slug
+)
+SELECT
+    inserted_correction.id,
+    inserted_correction.text_id,
+    inserted_correction.body,
+    inserted_correction.author,
+    inserted_correction.created_at,
+    bumped_article.title AS text_title,
+    bumped_article.title_slug AS text_title_slug
+FROM inserted_correction
+JOIN bumped_article ON
+    inserted_correction.text_id = bumped_article.id
@@ -0,0 +1,17 @@
+SELECT
+    id,
+    text_id,
+    author,
+    created_at,
+    title,
+    thumbnail AS "thumbnail_id",
+    lead_paragraph,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    text_type AS "text_type!: TextType",
+    tags
+FROM
+    text_revisions
+WHERE
+    text_id = $1
+ORDER BY
+    created_at DESC, id DESC
@@ -0,0 +1,24 @@
+INSERT INTO
+    text_revisions (
+        text_id,
+        author,
+        title,
+        thumbnail,
+        lead_paragraph,
+        text_body,
+        text_type,
+        tags
+    )
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7, $8)
+RETURNING
+    id,
+    text_id,
+    author,
+    created_at,
+    title,
+    thumbnail AS "thumbnail_id",
+    lead_paragraph,
+    text_body AS "text_body!: Json<Vec<Block>>",
+    text_type AS "text_type!: TextType",
+    tags
@@ -0,0 +1,13 @@
+SELECT
+    id,
+    text_id,
+    from_status AS "from_status!: TextStatus",
+    to_status AS "to_status!: TextStatus",
+    actor,
+    created_at
+FROM
+    text_status_transitions
+WHERE
+    text_id = $1
+ORDER BY
+    created_at DESC, id DESC
@@ -0,0 +1,17 @@
+SELECT
+    kind AS "kind!: TrashKind",
+    id AS "id!",
+    title AS "title!",
+    deleted_at AS "deleted_at!: DateTime<Local>",
+    deleted_at + make_interval(days => $1::integer) AS "purge_at!: DateTime<Local>"
+FROM (
+    SELECT 'Text' AS kind, id::text, title, deleted_at FROM articles WHERE deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'Page' AS kind, path AS id, title, deleted_at FROM pages WHERE deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'Image' AS kind, id::text, COALESCE(description, '') AS title, deleted_at FROM images WHERE deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'Audio' AS kind, id::text, title, deleted_at FROM audio WHERE deleted_at IS NOT NULL
+) AS trash
+ORDER BY
+    deleted_at DESC
@@ -0,0 +1,110 @@
+use std::{fs, str::FromStr};
+
+use rocket::{
+    State,
+    data::{Data, Limits},
+    http::{ContentType, Status},
+    response::Redirect,
+};
+use uuid::Uuid;
+
+use crate::{
+    database::{DatabaseHandler, models::audio::Audio},
+    defaults::DATA_DIR,
+    error::Error,
+    token::Claims,
+};
+
+/// The audio file is sent as the whole body, with its MIME type as the `Content-Type`, and is streamed to disk.
+/// It may be far larger than the forms of the site, so its size is limited by `file/<extension>`
+/// in the `limits` of `Rocket.toml`, e.g. `file/mp3`.
+#[post("/audio/upload?<title>&<description>", data = "<audio>")]
+pub async fn audio_upload(
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+    title: &str,
+    description: Option<&str>,
+    content_type: &ContentType,
+    limits: &Limits,
+    audio: Data<'_>,
+) -> Result<Redirect, Error> {
+    let content_type = format!("{}/{}", content_type.top(), content_type.sub());
+    let (mime_type, extension) = Audio::format(&content_type).ok_or(Error::create(
+        &format!("{}:{}", file!(), line!()),
+        "Sorry, only MP3, M4A, Ogg and WAV files can be uploaded!",
+        Status::BadRequest,
+    ))?;
+
+    if title.trim().is_empty() {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Sorry, the audio must have a title!",
+            Status::BadRequest,
+        ));
+    }
+
+    fs::create_dir_all(format!("{}/audio", DATA_DIR))?;
+    let id = Uuid::new_v4();
+    let path = Audio::path(id, extension);
+    let limit = limits
+        .find(["file", extension])
+        .or_else(|| limits.get("file"))
+        .unwrap_or(Limits::FILE);
+    if !audio.open(limit).into_file(&path).await?.is_complete() {
+        fs::remove_file(&path)?;
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            &format!("Sorry, the audio file may be at most {}!", limit),
+            Status::PayloadTooLarge,
+        ));
+    }
+
+    let description = description
+        .map(str::trim)
+        .filter(|description| !description.is_empty());
+    Audio::save_to_db(db, id, &claims.sub, title.trim(), description, mime_type).await?;
+
+    Ok(Redirect::to("/control-panel/audio"))
+}
+
+#[post("/audio/delete/<id>")]
+pub async fn audio_delete(
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+    id: &str,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Sorry, the action you are performing requires admin access!",
+            Status::Forbidden,
+        ));
+    }
+
+    let id_as_uuid = Uuid::from_str(id)?;
+
+    Audio::delete(db, id_as_uuid).await?;
+
+    Ok(Redirect::to("/control-panel/audio"))
+}
+
+#[post("/audio/restore/<id>")]
+pub async fn audio_restore(
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+    id: &str,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Sorry, the action you are performing requires admin access!",
+            Status::Forbidden,
+        ));
+    }
+
+    let id_as_uuid = Uuid::from_str(id)?;
+
+    Audio::restore(db, id_as_uuid).await?;
+
+    Ok(Redirect::to("/control-panel/trash"))
+}
@@ -0,0 +1,32 @@
+use rocket::{State, serde::json::Json};
+
+use crate::{
+    block_editor::{
+        Block, markdown,
+        validation::{BlockError, warn_blocks},
+    },
+    database::DatabaseHandler,
+    error::Error,
+    token::Claims,
+};
+
+/// Used by the block editor to show warnings about the blocks while they are edited, see `warn_blocks`.
+#[post("/block/warnings", format = "json", data = "<blocks>")]
+pub async fn block_warnings(
+    db: &State<DatabaseHandler>,
+    _claims: Claims,
+    blocks: Json<Vec<Block>>,
+) -> Json<Vec<BlockError>> {
+    Json(warn_blocks(db, &blocks).await)
+}
+
+/// Used by the block editor to turn a Markdown document into blocks, see `markdown::import`.
+/// The blocks are only added to the editor, so nothing is saved until the text or page is.
+#[post("/block/import-markdown", data = "<markdown>")]
+pub async fn block_import_markdown(
+    db: &State<DatabaseHandler>,
+    _claims: Claims,
+    markdown: String,
+) -> Result<Json<Vec<Block>>, Error> {
+    markdown::import(db, &markdown).await.map(Json)
+}
@@ -2,7 +2,7 @@ use std::str::FromStr;
 use tokio::task;
 
 use image::ImageFormat;
-use rocket::{State, form::Form, http::Status, response::Redirect};
+use rocket::{State, form::Form, http::Status, response::Redirect, serde::json::Json};
 use uuid::Uuid;
 
 use crate::{
@@ -46,7 +46,7 @@ pub async fn image_upload(
             .await;
 
     if image_status.is_err() {
-        Image::delete(db, image.id).await?;
+        Image::delete_permanently(db, image.id).await?;
         return Err(Error::create(
             "api::image::image_upload",
             format!(
@@ -81,3 +81,40 @@ pub async fn image_delete(
 
     Ok(Redirect::to("/control-panel/image-gallery"))
 }
+
+#[post("/image/restore/<id>")]
+pub async fn image_restore(
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+    id: &str,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Sorry, the action you are performing requires admin access!",
+            Status::Forbidden,
+        ));
+    }
+
+    let id_as_uuid = Uuid::from_str(id)?;
+
+    Image::restore(db, id_as_uuid).await?;
+
+    Ok(Redirect::to("/control-panel/trash"))
+}
+
+/// Used by the image picker in the block editor.
+/// Gets the images matching `q`, or all images if `q` is empty.
+#[get("/image/search?<q>")]
+pub async fn image_search(
+    db: &State<DatabaseHandler>,
+    _claims: Claims,
+    q: Option<&str>,
+) -> Result<Json<Vec<Image>>, Error> {
+    let images = match q.map(str::trim) {
+        Some(q) if !q.is_empty() => Image::search(db, q).await?,
+        _ => Image::get_all(db).await?,
+    };
+
+    Ok(Json(images))
+}
@@ -1,21 +1,43 @@
-use page::{page_edit, page_save};
-use rocket::Route;
+use page::{page_delete, page_edit, page_markdown, page_restore, page_save};
+use rocket::{Route, http::Header, serde::json::Json};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    block_editor::{
+        Block,
+        validation::{BlockError, validate_blocks},
+    },
+    database::DatabaseHandler,
+    error::Error,
+};
+
 use crate::api::{
+    audio::{audio_delete, audio_restore, audio_upload},
     auth::{auth_change_password, auth_change_password_other, auth_login, auth_logout},
+    block::{block_import_markdown, block_warnings},
     creator::{
         creator_demote, creator_lock, creator_new, creator_promote, creator_update_profile,
         creator_update_profile_picture,
     },
-    image::{image_delete, image_upload},
-    text::{text_edit, text_save, text_set_done_status, text_set_publish_status},
+    image::{image_delete, image_restore, image_search, image_upload},
+    poll::{poll_status, poll_vote},
+    snippet::{snippet_delete, snippet_edit, snippet_save},
+    text::{
+        text_autosave, text_autosave_discard, text_comment_add, text_comment_reopen,
+        text_comment_resolve, text_correction_add, text_delete, text_edit, text_markdown,
+        text_presence, text_presence_leave, text_preview_link_create, text_preview_link_revoke,
+        text_restore, text_restore_revision, text_save, text_set_publish_at, text_set_status,
+    },
 };
 
+pub mod audio;
 pub mod auth;
+pub mod block;
 pub mod creator;
 pub mod image;
 pub mod page;
+pub mod poll;
+pub mod snippet;
 pub mod text;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,14 +45,85 @@ pub struct ReturnRedirect {
     pub redirect: String,
 }
 
+/// Sent with `409 Conflict` when saving something that someone else has saved since it was loaded.
+/// `current` is the version that is saved now, so that the editor can show or overwrite it.
+#[derive(Debug, Serialize)]
+pub struct EditConflict<T> {
+    pub err_string: String,
+    pub current: T,
+}
+
+/// Sent with `422 Unprocessable Entity` when saving blocks that are invalid, so that the editor can point them out.
+#[derive(Debug, Serialize)]
+pub struct InvalidBlocks {
+    pub err_string: String,
+    pub errors: Vec<BlockError>,
+}
+
+/// A text or page as a Markdown file, see `block_editor::markdown::export`.
+#[derive(Debug, Responder)]
+#[response(content_type = "text/markdown")]
+pub struct MarkdownFile {
+    markdown: String,
+    content_disposition: Header<'static>,
+}
+
+impl MarkdownFile {
+    /// * `filename` without the `.md` extension.
+    pub fn new(markdown: String, filename: &str) -> Self {
+        Self {
+            markdown,
+            content_disposition: Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}.md\"", filename),
+            ),
+        }
+    }
+}
+
+/// Errors when saving a text or page from the editor.
+#[derive(Debug, Responder)]
+pub enum EditError<T: Serialize> {
+    #[response(status = 409, content_type = "json")]
+    Conflict(Json<EditConflict<T>>),
+    #[response(status = 422, content_type = "json")]
+    Invalid(Json<InvalidBlocks>),
+    Other(Error),
+}
+
+impl<T: Serialize> EditError<T> {
+    /// Checks the blocks of a text or page before it is saved, see `validate_blocks`.
+    pub async fn check_blocks(db: &DatabaseHandler, blocks: &[Block]) -> Result<(), Self> {
+        validate_blocks(db, blocks).await.map_err(|errors| {
+            Self::Invalid(Json(InvalidBlocks {
+                err_string: "Some blocks are invalid!".to_string(),
+                errors,
+            }))
+        })
+    }
+}
+
+impl<T: Serialize> From<Error> for EditError<T> {
+    fn from(value: Error) -> Self {
+        Self::Other(value)
+    }
+}
+
 /// These should be mounted on `/api`.
 pub fn get_all_routes() -> Vec<Route> {
     routes![
+        // -> /audio
+        audio_upload,
+        audio_delete,
+        audio_restore,
         // -> /auth
         auth_login,
         auth_logout,
         auth_change_password,
         auth_change_password_other,
+        // -> /block
+        block_warnings,
+        block_import_markdown,
         // -> /creator
         creator_new,
         creator_update_profile,
@@ -41,13 +134,39 @@ pub fn get_all_routes() -> Vec<Route> {
         // -> /image
         image_upload,
         image_delete,
+        image_restore,
+        image_search,
         // -> /text
         text_save,
         text_edit,
-        text_set_done_status,
-        text_set_publish_status,
+        text_set_status,
+        text_set_publish_at,
+        text_restore_revision,
+        text_comment_add,
+        text_comment_resolve,
+        text_comment_reopen,
+        text_correction_add,
+        text_preview_link_create,
+        text_preview_link_revoke,
+        text_delete,
+        text_restore,
+        text_presence,
+        text_autosave,
+        text_autosave_discard,
+        text_presence_leave,
+        text_markdown,
         // -> /page
         page_save,
         page_edit,
+        page_delete,
+        page_restore,
+        page_markdown,
+        // -> /poll
+        poll_vote,
+        poll_status,
+        // -> /snippet
+        snippet_save,
+        snippet_edit,
+        snippet_delete,
     ]
 }
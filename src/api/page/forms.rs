@@ -1,3 +1,4 @@
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
 use crate::block_editor::Block;
@@ -7,7 +8,15 @@ use crate::block_editor::Block;
 pub struct SaveOrEditPage<'a> {
     #[serde(rename = "old-path")]
     pub old_path: Option<&'a str>,
+    /// The `updated_at` of the page when it was loaded in the editor; this only needs to exist when editing.
+    #[serde(rename = "updated-at")]
+    pub updated_at: Option<DateTime<Local>>,
     pub path: &'a str,
     pub title: &'a str,
     pub blocks: Vec<Block>,
 }
+
+#[derive(Debug, FromForm)]
+pub struct OnlyPath<'a> {
+    pub path: &'a str,
+}
@@ -1,29 +1,44 @@
-use forms::SaveOrEditPage;
+use forms::{OnlyPath, SaveOrEditPage};
 
-use rocket::{State, http::Status, serde::json::Json};
+use rocket::{State, form::Form, http::Status, response::Redirect, serde::json::Json};
 
 use crate::{
+    block_editor::{Block, markdown},
     database::{DatabaseHandler, models::page::Page},
     error::Error,
     token::Claims,
 };
 
-use super::ReturnRedirect;
+use super::{EditConflict, EditError, MarkdownFile, ReturnRedirect};
 
 mod forms;
 
+/// Pages in the trash keep their path until they are purged,
+/// so tell the admin instead of failing on the unique path.
+async fn check_path_not_trashed(db: &DatabaseHandler, path: &str) -> Result<(), Error> {
+    if Page::is_trashed(db, path).await? {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "A page with this path is in the trash, restore or purge it first!",
+            Status::Conflict,
+        ));
+    }
+    Ok(())
+}
+
 #[post("/page/save", data = "<data>")]
 pub async fn page_save(
     data: Json<SaveOrEditPage<'_>>,
     db: &State<DatabaseHandler>,
     claims: Claims,
-) -> Result<Json<ReturnRedirect>, Error> {
+) -> Result<Json<ReturnRedirect>, EditError<Page>> {
     if !claims.admin {
         return Err(Error::create(
             &format!("{}:{}", file!(), line!()),
             "You need to be an admin to access this view!",
             Status::Unauthorized,
-        ));
+        )
+        .into());
     };
 
     if data.path.starts_with("/") {
@@ -31,16 +46,19 @@ pub async fn page_save(
             &format!("{}:{}", file!(), line!()),
             "Field `path` should not start with a slash!",
             Status::BadRequest,
-        ));
+        )
+        .into());
     }
 
-    let page = Page::create(data.path, data.title, data.blocks.clone());
+    let blocks = Block::prepare_for_saving(&claims.data, data.blocks.clone(), &[])?;
+    EditError::check_blocks(db, &blocks).await?;
+    check_path_not_trashed(db, data.path).await?;
+    let page = Page::create(data.path, data.title, blocks);
 
-    page.save_to_db(db).await.map(|page| {
-        Json(ReturnRedirect {
-            redirect: format!("/{}", page.path),
-        })
-    })
+    let page = page.save_to_db(db).await?;
+    Ok(Json(ReturnRedirect {
+        redirect: format!("/{}", page.path),
+    }))
 }
 
 #[post("/page/edit", data = "<data>")]
@@ -48,13 +66,14 @@ pub async fn page_edit(
     data: Json<SaveOrEditPage<'_>>,
     db: &State<DatabaseHandler>,
     claims: Claims,
-) -> Result<Json<ReturnRedirect>, Error> {
+) -> Result<Json<ReturnRedirect>, EditError<Page>> {
     if !claims.admin {
         return Err(Error::create(
             &format!("{}:{}", file!(), line!()),
             "You need to be an admin to access this view!",
             Status::Unauthorized,
-        ));
+        )
+        .into());
     };
 
     if data.path.starts_with("/") {
@@ -62,7 +81,8 @@ pub async fn page_edit(
             &format!("{}:{}", file!(), line!()),
             "Field `path` should not start with a slash!",
             Status::BadRequest,
-        ));
+        )
+        .into());
     }
 
     let old_path = match data.old_path {
@@ -72,21 +92,106 @@ pub async fn page_edit(
                 &format!("{}:{}", file!(), line!()),
                 "Field `old_path` (`old_path`) not specified!",
                 Status::BadRequest,
-            ));
+            )
+            .into());
         }
     };
 
+    let loaded_updated_at = match data.updated_at {
+        Some(updated_at) => updated_at,
+        None => {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Field `updated-at` (`updated_at`) not specified!",
+                Status::BadRequest,
+            )
+            .into());
+        }
+    };
+
+    let blocks = Block::prepare_for_saving(&claims.data, data.blocks.clone(), &[])?;
+    EditError::check_blocks(db, &blocks).await?;
+    if data.path != old_path {
+        check_path_not_trashed(db, data.path).await?;
+    }
     let page = Page::update_by_path(
         db,
         old_path,
         data.path,
         data.title,
-        sqlx::types::Json(data.blocks.clone()),
+        sqlx::types::Json(blocks),
+        Some(loaded_updated_at),
     );
 
-    page.await.map(|page| {
-        Json(ReturnRedirect {
+    match page.await {
+        Ok(page) => Ok(Json(ReturnRedirect {
             redirect: format!("/{}", page.path),
-        })
-    })
+        })),
+        Err(err) if err.status == Status::Conflict => {
+            Err(EditError::Conflict(Json(EditConflict {
+                err_string: err.err_string,
+                current: Page::get_by_path(db, old_path).await?,
+            })))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[post("/page/delete", data = "<form>")]
+pub async fn page_delete(
+    form: Form<OnlyPath<'_>>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+
+    Page::delete(db, form.path)
+        .await
+        .map(|_| Redirect::to("/control-panel/pages"))
+}
+
+#[post("/page/restore", data = "<form>")]
+pub async fn page_restore(
+    form: Form<OnlyPath<'_>>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+
+    Page::restore(db, form.path)
+        .await
+        .map(|_| Redirect::to("/control-panel/trash"))
+}
+
+#[get("/page/markdown?<path>")]
+pub async fn page_markdown(
+    path: &str,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<MarkdownFile, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+
+    let page = Page::get_by_path(db, path).await?;
+    Ok(MarkdownFile::new(
+        markdown::export(&page.title, None, &page.text_body),
+        &page.path.replace('/', "-"),
+    ))
 }
@@ -0,0 +1,5 @@
+#[derive(Debug, FromForm)]
+pub struct Vote {
+    /// The index of the chosen option, see `Poll::option_list`.
+    pub option: i32,
+}
@@ -0,0 +1,160 @@
+use std::{net::IpAddr, str::FromStr};
+
+use rocket::{
+    State,
+    form::Form,
+    http::{Cookie, CookieJar, SameSite, Status},
+    response::Redirect,
+    serde::json::Json,
+    time::Duration,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        DatabaseHandler,
+        models::poll::{PollVote, StoredPoll},
+    },
+    defaults::POLL_VOTER_COOKIE_DAYS,
+    error::Error,
+    rate_limit::RateLimit,
+};
+
+use self::forms::Vote;
+
+mod forms;
+
+/// The cookie with the random id that identifies the browser of a voter, see `PollVote`.
+const VOTER_COOKIE: &str = "poll-voter";
+
+/// Gets a published poll, or `404 Not Found`.
+async fn get_published_poll(db: &DatabaseHandler, id: &str) -> Result<StoredPoll, Error> {
+    let not_found = || {
+        Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "There is no such poll!",
+            Status::NotFound,
+        )
+    };
+
+    let id = Uuid::from_str(id).map_err(|_| not_found())?;
+    match StoredPoll::get_by_id(db, id).await? {
+        Some(poll) if poll.is_published => Ok(poll),
+        _ => Err(not_found()),
+    }
+}
+
+/// Gets the voter id from the cookie of the browser, if it has one.
+fn voter(jar: &CookieJar<'_>) -> Option<Uuid> {
+    jar.get(VOTER_COOKIE)
+        .and_then(|cookie| Uuid::from_str(cookie.value()).ok())
+}
+
+/// Votes in a poll, and goes back to the poll.
+/// Every browser may only vote once per poll, and every IP address only a few times within a while, see `RateLimit`.
+#[post("/poll/<id>/vote", data = "<form>")]
+pub async fn poll_vote(
+    db: &State<DatabaseHandler>,
+    rate_limit: &State<RateLimit>,
+    jar: &CookieJar<'_>,
+    ip: IpAddr,
+    id: &str,
+    form: Form<Vote>,
+) -> Result<Redirect, Error> {
+    let stored_poll = get_published_poll(db, id).await?;
+    let poll_id = stored_poll.poll.uuid().unwrap_or_default();
+
+    if stored_poll.poll.is_closed() {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "The poll is closed!",
+            Status::Forbidden,
+        ));
+    }
+
+    if !(0..stored_poll.poll.option_list().len() as i32).contains(&form.option) {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "There is no such option in the poll!",
+            Status::BadRequest,
+        ));
+    }
+
+    if !rate_limit.hit(ip) {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Too many votes from this address, try again later!",
+            Status::TooManyRequests,
+        ));
+    }
+
+    let voter = match voter(jar) {
+        Some(voter) => voter,
+        None => {
+            let voter = Uuid::new_v4();
+            jar.add(
+                Cookie::build((VOTER_COOKIE, voter.to_string()))
+                    .same_site(SameSite::Lax)
+                    .secure(true)
+                    .http_only(true)
+                    .max_age(Duration::days(POLL_VOTER_COOKIE_DAYS)),
+            );
+            voter
+        }
+    };
+
+    if !PollVote::cast(db, poll_id, form.option, voter).await? {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You have already voted in this poll!",
+            Status::Conflict,
+        ));
+    }
+
+    Ok(Redirect::to(format!(
+        "{}#poll-{}",
+        stored_poll.url, poll_id
+    )))
+}
+
+/// Whether the reader has voted in a poll, and the results if they have or the poll is closed.
+#[derive(Debug, Serialize)]
+pub struct PollStatus {
+    pub has_voted: bool,
+    pub is_closed: bool,
+    pub options: Vec<String>,
+    /// The number of votes for each option, or `None` if the reader may not see them yet.
+    pub votes: Option<Vec<i64>>,
+}
+
+#[get("/poll/<id>")]
+pub async fn poll_status(
+    db: &State<DatabaseHandler>,
+    jar: &CookieJar<'_>,
+    id: &str,
+) -> Result<Json<PollStatus>, Error> {
+    let stored_poll = get_published_poll(db, id).await?;
+    let poll_id = stored_poll.poll.uuid().unwrap_or_default();
+
+    let has_voted = match voter(jar) {
+        Some(voter) => PollVote::has_voted(db, poll_id, voter).await?,
+        None => false,
+    };
+    let is_closed = stored_poll.poll.is_closed();
+
+    Ok(Json(PollStatus {
+        has_voted,
+        is_closed,
+        options: stored_poll
+            .poll
+            .option_list()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        votes: match has_voted || is_closed {
+            true => Some(stored_poll.results(db).await?),
+            false => None,
+        },
+    }))
+}
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::block_editor::Block;
+
+#[derive(Debug, Deserialize)]
+pub struct SaveOrEditSnippet<'a> {
+    /// The id of the snippet; this only needs to exist when editing.
+    pub id: Option<&'a str>,
+    /// The `updated_at` of the snippet when it was loaded in the editor; this only needs to exist when editing.
+    #[serde(rename = "updated-at")]
+    pub updated_at: Option<DateTime<Local>>,
+    pub name: &'a str,
+    pub blocks: Vec<Block>,
+}
@@ -0,0 +1,164 @@
+use std::str::FromStr;
+
+use forms::SaveOrEditSnippet;
+
+use rocket::{State, http::Status, response::Redirect, serde::json::Json};
+use uuid::Uuid;
+
+use crate::{
+    block_editor::{Block, snippet::Snippet as SnippetBlock, validation::BlockError},
+    database::{DatabaseHandler, models::snippet::Snippet},
+    error::Error,
+    token::Claims,
+};
+
+use super::{EditConflict, EditError, InvalidBlocks, ReturnRedirect};
+
+mod forms;
+
+/// Checks the name and blocks of a snippet before it is saved.
+/// A snippet cannot contain other snippets, as they would not be shown.
+async fn check_snippet(
+    db: &DatabaseHandler,
+    data: &SaveOrEditSnippet<'_>,
+    blocks: &[Block],
+) -> Result<(), EditError<Snippet>> {
+    if data.name.trim().is_empty() {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Field `name` must not be empty!",
+            Status::BadRequest,
+        )
+        .into());
+    }
+
+    let nested = blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| block.downcast_ref::<SnippetBlock>().is_some())
+        .map(|(index, block)| BlockError {
+            index,
+            block_type: block.type_name(),
+            field: "id",
+            message: "Ett gemensamt block kan inte innehålla andra gemensamma block.".to_string(),
+        })
+        .collect::<Vec<BlockError>>();
+    if !nested.is_empty() {
+        return Err(EditError::Invalid(Json(InvalidBlocks {
+            err_string: "Some blocks are invalid!".to_string(),
+            errors: nested,
+        })));
+    }
+
+    EditError::check_blocks(db, blocks).await
+}
+
+#[post("/snippet/save", data = "<data>")]
+pub async fn snippet_save(
+    data: Json<SaveOrEditSnippet<'_>>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Json<ReturnRedirect>, EditError<Snippet>> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        )
+        .into());
+    };
+
+    let blocks = Block::prepare_for_saving(&claims.data, data.blocks.clone(), &[])?;
+    check_snippet(db, &data, &blocks).await?;
+
+    Snippet::insert(db, data.name.trim(), blocks).await?;
+    Ok(Json(ReturnRedirect {
+        redirect: "/control-panel/snippets".to_string(),
+    }))
+}
+
+#[post("/snippet/edit", data = "<data>")]
+pub async fn snippet_edit(
+    data: Json<SaveOrEditSnippet<'_>>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Json<ReturnRedirect>, EditError<Snippet>> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        )
+        .into());
+    };
+
+    let id = match data.id {
+        Some(id) => Uuid::from_str(id).map_err(Error::from)?,
+        None => {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Field `id` not specified!",
+                Status::BadRequest,
+            )
+            .into());
+        }
+    };
+
+    let loaded_updated_at = match data.updated_at {
+        Some(updated_at) => updated_at,
+        None => {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Field `updated-at` (`updated_at`) not specified!",
+                Status::BadRequest,
+            )
+            .into());
+        }
+    };
+
+    let blocks = Block::prepare_for_saving(&claims.data, data.blocks.clone(), &[])?;
+    check_snippet(db, &data, &blocks).await?;
+
+    match Snippet::update(db, id, data.name.trim(), blocks, Some(loaded_updated_at)).await {
+        Ok(_) => Ok(Json(ReturnRedirect {
+            redirect: "/control-panel/snippets".to_string(),
+        })),
+        Err(err) if err.status == Status::Conflict => {
+            Err(EditError::Conflict(Json(EditConflict {
+                err_string: err.err_string,
+                current: Snippet::get_by_id(db, id).await?,
+            })))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Snippets that are in use cannot be deleted, as they would silently disappear from the texts and pages.
+#[post("/snippet/delete/<id>")]
+pub async fn snippet_delete(
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+    id: &str,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Sorry, the action you are performing requires admin access!",
+            Status::Forbidden,
+        ));
+    }
+
+    let id_as_uuid = Uuid::from_str(id)?;
+
+    if !Snippet::usage(db, id_as_uuid).await?.is_empty() {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "The snippet is used by texts or pages, and cannot be deleted!",
+            Status::Conflict,
+        ));
+    }
+
+    Snippet::delete(db, id_as_uuid).await?;
+
+    Ok(Redirect::to("/control-panel/snippets"))
+}
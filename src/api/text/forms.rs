@@ -1,3 +1,4 @@
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
 use crate::{block_editor::Block, database::models::article::TextType};
@@ -8,6 +9,9 @@ pub struct SaveOrEditText<'a> {
     /// This only needs to exist when editing an article.
     #[serde(rename = "text-id")]
     pub text_id: Option<i32>,
+    /// The `updated_at` of the text when it was loaded in the editor; this only needs to exist when editing.
+    #[serde(rename = "updated-at")]
+    pub updated_at: Option<DateTime<Local>>,
     #[serde(rename = "text-type")]
     pub text_type: TextType,
     pub title: &'a str,
@@ -16,6 +20,9 @@ pub struct SaveOrEditText<'a> {
     pub leading_paragraph: &'a str,
     pub blocks: Vec<Block>,
     pub tags: &'a str,
+    /// Usernames of the co-authors, separated by semicolons; left out to keep the co-authors of the text.
+    #[serde(rename = "co-authors", default)]
+    pub co_authors: Option<&'a str>,
 }
 
 #[derive(Debug, FromForm)]
@@ -23,3 +30,66 @@ pub struct OnlyTextId {
     #[field(name = "text-id")]
     pub text_id: i32,
 }
+
+/// Like `OnlyTextId`, but the text may not have been saved yet.
+#[derive(Debug, FromForm)]
+pub struct OptionalTextId {
+    #[field(name = "text-id")]
+    pub text_id: Option<i32>,
+}
+
+#[derive(Debug, FromForm)]
+pub struct OnlyRevisionId {
+    #[field(name = "revision-id")]
+    pub revision_id: i32,
+}
+
+#[derive(Debug, FromForm)]
+pub struct SchedulePublish<'a> {
+    #[field(name = "text-id")]
+    pub text_id: i32,
+    /// The value of a `datetime-local` input, e.g. `2024-05-01T06:00`; empty cancels the schedule.
+    #[field(name = "publish-at")]
+    pub publish_at: &'a str,
+}
+
+#[derive(Debug, FromForm)]
+pub struct AddReviewComment<'a> {
+    #[field(name = "text-id")]
+    pub text_id: i32,
+    /// The comment to reply to; left out when starting a new thread.
+    #[field(name = "parent-id")]
+    pub parent_id: Option<i32>,
+    /// The block the comment is about; left out (or empty) for the whole text.
+    #[field(name = "block-index")]
+    pub block_index: Option<i32>,
+    pub body: &'a str,
+}
+
+#[derive(Debug, FromForm)]
+pub struct OnlyCommentId {
+    #[field(name = "comment-id")]
+    pub comment_id: i32,
+}
+
+#[derive(Debug, FromForm)]
+pub struct AddCorrection<'a> {
+    #[field(name = "text-id")]
+    pub text_id: i32,
+    pub body: &'a str,
+}
+
+#[derive(Debug, FromForm)]
+pub struct NewPreviewLink {
+    #[field(name = "text-id")]
+    pub text_id: i32,
+    /// For how many days the link is valid.
+    #[field(name = "valid-days")]
+    pub valid_days: i64,
+}
+
+#[derive(Debug, FromForm)]
+pub struct OnlyPreviewLinkId {
+    #[field(name = "preview-link-id")]
+    pub preview_link_id: i32,
+}
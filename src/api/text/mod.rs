@@ -1,57 +1,112 @@
 use std::str::FromStr;
 
+use chrono::{Local, NaiveDateTime, TimeZone};
 use rocket::{State, form::Form, http::Status, response::Redirect, serde::json::Json};
 use uuid::Uuid;
 
 use crate::{
+    block_editor::{Block, markdown},
     database::{
         DatabaseHandler,
-        models::{article::Text, image::Image},
+        models::{
+            article::Text, autosave::Autosave, correction::Correction, creator::Creator,
+            image::Image, preview_link::PreviewLink, review_comment::ReviewComment,
+            revision::TextRevision, text_status::TextStatus,
+        },
     },
     error::Error,
+    presence::Presence,
     token::Claims,
 };
 
-use self::forms::{OnlyTextId, SaveOrEditText};
+use self::forms::{
+    AddCorrection, AddReviewComment, NewPreviewLink, OnlyCommentId, OnlyPreviewLinkId,
+    OnlyRevisionId, OnlyTextId, OptionalTextId, SaveOrEditText, SchedulePublish,
+};
 
-use super::ReturnRedirect;
+use super::{EditConflict, EditError, MarkdownFile, ReturnRedirect};
 
 pub mod forms;
 
+/// Splits the semicolon separated `co-authors` field into a list of usernames.
+/// The `author` and duplicates are left out.
+fn split_co_authors(author: &str, co_authors: &str) -> Vec<String> {
+    let mut split: Vec<String> = Vec::new();
+
+    for username in co_authors.split(';').map(str::trim) {
+        if username.is_empty() || username == author || split.iter().any(|u| u == username) {
+            continue;
+        }
+        split.push(username.to_string());
+    }
+
+    split
+}
+
+/// Parses the semicolon separated `co-authors` field into a list of usernames, see `split_co_authors`.
+/// Every co-author must exist.
+async fn parse_co_authors(
+    db: &DatabaseHandler,
+    author: &str,
+    co_authors: &str,
+) -> Result<Vec<String>, Error> {
+    let parsed = split_co_authors(author, co_authors);
+
+    for username in &parsed {
+        if Creator::get_by_username(db, username).await.is_err() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                &format!("Co-author `{}` does not exist!", username),
+                Status::BadRequest,
+            ));
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Parses the semicolon separated `tags` field into a list of tags.
+fn parse_tags(tags: &str) -> Vec<String> {
+    match tags.is_empty() {
+        true => Vec::new(),
+        false => tags.split(';').map(String::from).collect::<Vec<String>>(),
+    }
+}
+
 #[post("/text/save", data = "<data>")]
 pub async fn text_save(
     data: Json<SaveOrEditText<'_>>,
     db: &State<DatabaseHandler>,
     claims: Claims,
-) -> Result<Json<ReturnRedirect>, Error> {
-    let tags = match data.tags.is_empty() {
-        true => Vec::new(),
-        false => data
-            .tags
-            .split(';')
-            .map(String::from)
-            .collect::<Vec<String>>(),
-    };
+) -> Result<Json<ReturnRedirect>, EditError<Text>> {
+    let tags = parse_tags(data.tags);
 
-    println!("{:?}", data.blocks);
+    let blocks = Block::prepare_for_saving(&claims.data, data.blocks.clone(), &[])?;
+    EditError::check_blocks(db, &blocks).await?;
 
-    // TODO: check if its valid
-    // let parsed_body_json: Vec<Block> = serde_json::from_str(form.text_body).unwrap();
+    let co_authors = parse_co_authors(
+        db,
+        &claims.data.username,
+        data.co_authors.unwrap_or_default(),
+    )
+    .await?;
 
     let text = Text::create(
         data.title,
         &claims.data.username,
+        co_authors,
         data.leading_paragraph,
-        data.blocks.clone(),
+        blocks,
         data.text_type,
         tags,
     );
 
-    text.save_to_db(db).await.map(|text| {
-        Json(ReturnRedirect {
-            redirect: format!("/t/{}/{}", text.id, text.title_slug),
-        })
-    })
+    let text = text.save_to_db(db).await?;
+    Autosave::discard(db, &claims.sub, None).await?;
+
+    Ok(Json(ReturnRedirect {
+        redirect: format!("/t/{}/{}", text.id, text.title_slug),
+    }))
 }
 
 #[post("/text/edit", format = "json", data = "<data>")]
@@ -59,15 +114,8 @@ pub async fn text_edit(
     data: Json<SaveOrEditText<'_>>,
     db: &State<DatabaseHandler>,
     claims: Claims,
-) -> Result<Json<ReturnRedirect>, Error> {
-    let tags = match data.tags.is_empty() {
-        true => Vec::new(),
-        false => data
-            .tags
-            .split(';')
-            .map(String::from)
-            .collect::<Vec<String>>(),
-    };
+) -> Result<Json<ReturnRedirect>, EditError<Text>> {
+    let tags = parse_tags(data.tags);
 
     let text_id = match data.text_id {
         Some(text_id) => text_id,
@@ -76,30 +124,63 @@ pub async fn text_edit(
                 &format!("{}:{}", file!(), line!()),
                 "Field `text-id` (`text_id`) not specified!",
                 Status::BadRequest,
-            ));
+            )
+            .into());
+        }
+    };
+
+    let loaded_updated_at = match data.updated_at {
+        Some(updated_at) => updated_at,
+        None => {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Field `updated-at` (`updated_at`) not specified!",
+                Status::BadRequest,
+            )
+            .into());
         }
     };
 
     let current_text = Text::get_by_id(db, text_id, false).await?;
 
-    if current_text.author != claims.sub && !claims.admin {
+    if !current_text.is_author(&claims.sub) && !claims.admin {
         return Err(Error::create(
             &format!("{}:{}", file!(), line!()),
             "Must be owner of text or publisher to edit!",
             Status::Unauthorized,
-        ));
+        )
+        .into());
     }
 
-    if current_text.is_published && !claims.admin {
+    if !current_text.status.is_editable_by_author() && !claims.admin {
         return Err(Error::create(
             &format!("{}:{}", file!(), line!()),
-            "Cannot edit published text if not publisher!",
+            "Cannot edit approved, published or archived text if not publisher!",
             Status::Unauthorized,
-        ));
+        )
+        .into());
+    }
+
+    // Only the main author and publishers may change who the co-authors are.
+    let co_authors = match data.co_authors {
+        Some(co_authors) => parse_co_authors(db, &current_text.author, co_authors).await?,
+        None => current_text.co_authors.clone(),
+    };
+    if co_authors != current_text.co_authors && current_text.author != claims.sub && !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Must be the main author of the text or publisher to change its co-authors!",
+            Status::Unauthorized,
+        )
+        .into());
     }
+    let blocks =
+        Block::prepare_for_saving(&claims.data, data.blocks.clone(), &current_text.text_body)?;
+    EditError::check_blocks(db, &blocks).await?;
 
     let updated_text = Text::update_by_id(
         db,
+        &claims.sub,
         text_id,
         data.title,
         match Uuid::from_str(data.thumbnail) {
@@ -110,39 +191,374 @@ pub async fn text_edit(
             _ => None,
         },
         data.leading_paragraph,
-        sqlx::types::Json(data.blocks.clone()),
+        sqlx::types::Json(blocks),
         data.text_type,
         &tags,
+        &co_authors,
+        Some(loaded_updated_at),
     )
-    .await?;
+    .await;
+
+    let updated_text = match updated_text {
+        Ok(updated_text) => updated_text,
+        Err(err) if err.status == Status::Conflict => {
+            return Err(EditError::Conflict(Json(EditConflict {
+                err_string: err.err_string,
+                current: Text::get_by_id(db, text_id, false).await?,
+            })));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    Autosave::discard(db, &claims.sub, Some(text_id)).await?;
 
     Ok(Json(ReturnRedirect {
         redirect: format!("/t/{}/{}", updated_text.id, updated_text.title_slug),
     }))
 }
 
-#[post("/text/set-publish-status/<publish_status>", data = "<form>")]
-pub async fn text_set_publish_status(
+#[post("/text/set-status/<status>", data = "<form>")]
+pub async fn text_set_status(
     form: Form<OnlyTextId>,
-    publish_status: bool,
+    status: TextStatus,
     db: &State<DatabaseHandler>,
     claims: Claims,
 ) -> Result<Redirect, Error> {
     let text = Text::get_by_id(db, form.text_id, false).await?;
-    Text::set_publish_status(db, &claims.data, form.text_id, publish_status)
+    Text::set_status(db, &claims.data, form.text_id, status)
         .await
         .map(|_| Redirect::to(format!("/t/{}/{}", text.id, text.title_slug)))
 }
 
-#[post("/text/set-done-status/<done_status>", data = "<form>")]
-pub async fn text_set_done_status(
-    form: Form<OnlyTextId>,
-    done_status: bool,
+#[post("/text/set-publish-at", data = "<form>")]
+pub async fn text_set_publish_at(
+    form: Form<SchedulePublish<'_>>,
     db: &State<DatabaseHandler>,
     claims: Claims,
 ) -> Result<Redirect, Error> {
     let text = Text::get_by_id(db, form.text_id, false).await?;
-    Text::set_done_status(db, &claims.data, form.text_id, done_status)
+
+    // An empty value cancels the scheduled publishing.
+    let publish_at = match form.publish_at.is_empty() {
+        true => None,
+        false => {
+            let naive = NaiveDateTime::parse_from_str(form.publish_at, "%Y-%m-%dT%H:%M")
+                .or_else(|_| NaiveDateTime::parse_from_str(form.publish_at, "%Y-%m-%dT%H:%M:%S"))
+                .map_err(|_| {
+                    Error::create(
+                        &format!("{}:{}", file!(), line!()),
+                        "Field `publish-at` is not a valid date and time!",
+                        Status::BadRequest,
+                    )
+                })?;
+
+            let publish_at = Local
+                .from_local_datetime(&naive)
+                .single()
+                .ok_or(Error::create(
+                    &format!("{}:{}", file!(), line!()),
+                    "Field `publish-at` is ambiguous or does not exist in the local time zone!",
+                    Status::BadRequest,
+                ))?;
+
+            Some(publish_at)
+        }
+    };
+
+    Text::set_publish_at(db, &claims.data, form.text_id, publish_at)
         .await
         .map(|_| Redirect::to(format!("/t/{}/{}", text.id, text.title_slug)))
 }
+
+#[post("/text/restore-revision", data = "<form>")]
+pub async fn text_restore_revision(
+    form: Form<OnlyRevisionId>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Must be publisher to restore a revision!",
+            Status::Unauthorized,
+        ));
+    }
+
+    let revision = TextRevision::get_by_id(db, form.revision_id).await?;
+    // Revisions do not track the byline, so the current co-authors are kept.
+    let text = Text::get_by_id(db, revision.text_id, false).await?;
+    let blocks = Block::prepare_for_saving(&claims.data, revision.text_body.0, &text.text_body)?;
+
+    // Restoring is saved like any other edit, so the restore itself also becomes a revision.
+    let restored_text = Text::update_by_id(
+        db,
+        &claims.sub,
+        revision.text_id,
+        &revision.title,
+        revision.thumbnail_id,
+        &revision.lead_paragraph,
+        sqlx::types::Json(blocks),
+        revision.text_type,
+        &revision.tags,
+        &text.co_authors,
+        None,
+    )
+    .await?;
+
+    Ok(Redirect::to(format!(
+        "/t/{}/{}",
+        restored_text.id, restored_text.title_slug
+    )))
+}
+
+#[post("/text/comment/add", data = "<form>")]
+pub async fn text_comment_add(
+    form: Form<AddReviewComment<'_>>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    ReviewComment::add(
+        db,
+        &claims.data,
+        form.text_id,
+        form.parent_id,
+        form.block_index,
+        form.body,
+    )
+    .await
+    .map(|comment| {
+        Redirect::to(format!(
+            "/control-panel/edit/{}#review-comments",
+            comment.text_id
+        ))
+    })
+}
+
+#[post("/text/comment/resolve", data = "<form>")]
+pub async fn text_comment_resolve(
+    form: Form<OnlyCommentId>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    ReviewComment::set_resolved(db, &claims.data, form.comment_id, true)
+        .await
+        .map(|comment| {
+            Redirect::to(format!(
+                "/control-panel/edit/{}#review-comments",
+                comment.text_id
+            ))
+        })
+}
+
+#[post("/text/comment/reopen", data = "<form>")]
+pub async fn text_comment_reopen(
+    form: Form<OnlyCommentId>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    ReviewComment::set_resolved(db, &claims.data, form.comment_id, false)
+        .await
+        .map(|comment| {
+            Redirect::to(format!(
+                "/control-panel/edit/{}#review-comments",
+                comment.text_id
+            ))
+        })
+}
+
+#[post("/text/correction/add", data = "<form>")]
+pub async fn text_correction_add(
+    form: Form<AddCorrection<'_>>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    Correction::add(db, &claims.data, form.text_id, form.body)
+        .await
+        .map(|correction| {
+            Redirect::to(format!(
+                "/t/{}/{}#corrections",
+                correction.text_id, correction.text_title_slug
+            ))
+        })
+}
+
+#[post("/text/preview-link/create", data = "<form>")]
+pub async fn text_preview_link_create(
+    form: Form<NewPreviewLink>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    PreviewLink::create(db, &claims.data, form.text_id, form.valid_days)
+        .await
+        .map(|link| {
+            Redirect::to(format!(
+                "/control-panel/edit/{}#preview-links",
+                link.text_id
+            ))
+        })
+}
+
+#[post("/text/preview-link/revoke", data = "<form>")]
+pub async fn text_preview_link_revoke(
+    form: Form<OnlyPreviewLinkId>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    PreviewLink::revoke(db, &claims.data, form.preview_link_id)
+        .await
+        .map(|_| Redirect::to("/control-panel/preview-links"))
+}
+
+#[post("/text/delete", data = "<form>")]
+pub async fn text_delete(
+    form: Form<OnlyTextId>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    let text = Text::get_by_id(db, form.text_id, false).await?;
+
+    // Authors may only trash texts they may still edit, e.g. not published ones.
+    if !claims.admin && (!text.is_author(&claims.sub) || !text.status.is_editable_by_author()) {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Must be owner of an unpublished text or publisher to delete!",
+            Status::Unauthorized,
+        ));
+    }
+
+    Text::delete(db, text.id)
+        .await
+        .map(|_| Redirect::to("/control-panel"))
+}
+
+#[post("/text/restore", data = "<form>")]
+pub async fn text_restore(
+    form: Form<OnlyTextId>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Must be publisher to restore a text!",
+            Status::Unauthorized,
+        ));
+    }
+
+    Text::restore(db, form.text_id)
+        .await
+        .map(|_| Redirect::to("/control-panel/trash"))
+}
+
+/// Pinged by the editor while a text is open.
+/// Returns the display names of everyone else who has the text open.
+#[post("/text/presence", data = "<form>")]
+pub async fn text_presence(
+    form: Form<OnlyTextId>,
+    db: &State<DatabaseHandler>,
+    presence: &State<Presence>,
+    claims: Claims,
+) -> Result<Json<Vec<String>>, Error> {
+    let text = Text::get_by_id(db, form.text_id, false).await?;
+
+    if !text.is_author(&claims.sub) && !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Must be owner of text or publisher to see who has it open!",
+            Status::Unauthorized,
+        ));
+    }
+
+    presence.touch(form.text_id, &claims.sub, &claims.data.display_name);
+    Ok(Json(presence.others(form.text_id, &claims.sub)))
+}
+
+/// Sent by the editor when it is closed.
+#[post("/text/presence/leave", data = "<form>")]
+pub async fn text_presence_leave(
+    form: Form<OnlyTextId>,
+    presence: &State<Presence>,
+    claims: Claims,
+) {
+    presence.leave(form.text_id, &claims.sub);
+}
+
+/// Stores an unsaved working copy of a text from the editor, see `Autosave`.
+/// Leave out `text-id` for a text that has not been saved yet.
+#[post("/text/autosave", format = "json", data = "<data>")]
+pub async fn text_autosave(
+    data: Json<SaveOrEditText<'_>>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Json<Autosave>, Error> {
+    let (author, co_authors) = match data.text_id {
+        Some(text_id) => {
+            let text = Text::get_by_id(db, text_id, false).await?;
+            if !claims.admin
+                && (!text.is_author(&claims.sub) || !text.status.is_editable_by_author())
+            {
+                return Err(Error::create(
+                    &format!("{}:{}", file!(), line!()),
+                    "Must be able to edit the text to autosave it!",
+                    Status::Unauthorized,
+                ));
+            }
+            (text.author, text.co_authors)
+        }
+        None => (claims.sub.clone(), Vec::new()),
+    };
+
+    Autosave::save(
+        db,
+        &claims.sub,
+        data.text_id,
+        data.title,
+        data.leading_paragraph,
+        data.blocks.clone(),
+        data.text_type,
+        &parse_tags(data.tags),
+        &match data.co_authors {
+            Some(data_co_authors) => split_co_authors(&author, data_co_authors),
+            None => co_authors,
+        },
+        data.updated_at,
+    )
+    .await
+    .map(Json)
+}
+
+/// Throws away the working copy of a text, and goes back to the editor.
+#[post("/text/autosave/discard", data = "<form>")]
+pub async fn text_autosave_discard(
+    form: Form<OptionalTextId>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Redirect, Error> {
+    Autosave::discard(db, &claims.sub, form.text_id).await?;
+
+    Ok(Redirect::to(match form.text_id {
+        Some(text_id) => format!("/control-panel/edit/{}", text_id),
+        None => "/control-panel/editor".into(),
+    }))
+}
+
+#[get("/text/<id>/markdown")]
+pub async fn text_markdown(
+    id: i32,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<MarkdownFile, Error> {
+    let text = Text::get_by_id(db, id, false).await?;
+
+    if !text.is_author(&claims.sub) && !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Must be owner of text or publisher to export!",
+            Status::Unauthorized,
+        ));
+    }
+
+    Ok(MarkdownFile::new(
+        markdown::export(&text.title, Some(&text.lead_paragraph), &text.text_body),
+        &format!("{}-{}", text.id, text.title_slug),
+    ))
+}
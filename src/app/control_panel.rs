@@ -1,14 +1,29 @@
 use crate::anyresponder::AnyResponder;
+use crate::block_editor::render::{RenderContext, RenderTarget};
+use crate::database::models::audio::Audio;
+use crate::database::models::autosave::Autosave;
 use crate::database::models::image::Image;
 use crate::database::models::page::Page;
+use crate::database::models::poll::StoredPoll;
+use crate::database::models::preview_link::PreviewLink;
+use crate::database::models::review_comment::ReviewComment;
+use crate::database::models::revision::TextRevision;
+use crate::database::models::snippet::Snippet;
+use crate::database::models::text_status::TextStatus;
+use crate::database::models::trash::TrashedItem;
 use crate::database::{DatabaseHandler, models::article::Text};
+use crate::defaults::PREVIEW_LINK_MAX_DAYS;
 use crate::flash_msg::FlashMsg;
+use crate::presence::Presence;
 use crate::{database::models::creator::Creator, error::Error, token::Claims};
-use rocket::http::Status;
+use rocket::http::{Header, Status};
 use rocket::request::FlashMessage;
 use rocket::response::Redirect;
 use rocket::{Route, State};
 use rocket_dyn_templates::{Template, context};
+use serde::Serialize;
+use std::str::FromStr;
+use uuid::Uuid;
 
 #[get("/")]
 async fn control_panel(
@@ -19,7 +34,7 @@ async fn control_panel(
     let creator = Creator::get_by_username(db, &claims.data.username).await?;
     let published_texts = Text::get_by_author(db, &claims.data.username, true).await?;
     let unpublished_texts = Text::get_by_author(db, &claims.data.username, false).await?;
-    let done_unpublished_texts_count = Text::get_all_done_unpublished_count(db).await?;
+    let in_review_texts_count = Text::get_status_count(db, TextStatus::InReview).await?;
 
     let all_creator_usernames = Creator::get_all(db)
         .await?
@@ -31,7 +46,7 @@ async fn control_panel(
 
     Ok(Template::render(
         "control_panel/main",
-        context! { creator, published_texts, unpublished_texts, all_creator_usernames, done_unpublished_texts_count, flash, is_admin: claims.data.is_publisher() },
+        context! { creator, published_texts, unpublished_texts, all_creator_usernames, in_review_texts_count, flash, is_admin: claims.data.is_publisher() },
     ))
 }
 
@@ -51,11 +66,8 @@ fn login_page(
     Ok(AnyResponder::from(redirect))
 }
 
-#[get("/preview-done-unpublished")]
-async fn preview_done_unpublished(
-    claims: Claims,
-    db: &State<DatabaseHandler>,
-) -> Result<Template, Error> {
+#[get("/review-queue")]
+async fn review_queue(claims: Claims, db: &State<DatabaseHandler>) -> Result<Template, Error> {
     if !claims.admin {
         return Err(Error::create(
             &format!("{}:{}", file!(), line!()),
@@ -64,11 +76,13 @@ async fn preview_done_unpublished(
         ));
     };
 
-    let texts = Text::get_all_done_unpublished(db).await?;
+    let in_review_texts = Text::get_by_status(db, &[TextStatus::InReview]).await?;
+    let approved_texts = Text::get_by_status(db, &[TextStatus::Approved]).await?;
+    let changes_requested_texts = Text::get_by_status(db, &[TextStatus::ChangesRequested]).await?;
 
     Ok(Template::render(
-        "control_panel/preview_done_unpublished",
-        context! { creator: claims.data, texts },
+        "control_panel/review_queue",
+        context! { creator: claims.data, in_review_texts, approved_texts, changes_requested_texts },
     ))
 }
 
@@ -95,6 +109,24 @@ async fn image_gallery(
     ))
 }
 
+#[get("/audio")]
+async fn audio_archive(claims: Claims, db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    // The address and duration are not stored, so they are added for the template.
+    let audio = Audio::get_all(db)
+        .await?
+        .into_iter()
+        .map(|audio| {
+            let (url, duration) = (audio.url(), audio.formatted_duration());
+            context! { audio, url, duration }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Template::render(
+        "control_panel/audio",
+        context! { creator: &claims.data, audio, is_admin: claims.data.is_publisher() },
+    ))
+}
+
 #[get("/account-manager")]
 async fn account_manager(
     claims: Claims,
@@ -171,44 +203,337 @@ async fn page_editor_path(
 
     Ok(Template::render(
         "control_panel/page_editor",
-        context! { page },
+        context! { page, is_editing: true },
     ))
 }
 
-#[get("/editor")]
-fn editor(claims: Claims) -> Template {
-    Template::render(
+#[get("/snippets")]
+async fn snippet_manager(claims: Claims, db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+
+    let mut snippets = Vec::new();
+    for snippet in Snippet::get_all(db).await? {
+        let usage = Snippet::usage(db, snippet.id).await?;
+        snippets.push(context! { snippet, usage });
+    }
+
+    Ok(Template::render(
+        "control_panel/snippet_manager",
+        context! { creator: &claims.data, snippets },
+    ))
+}
+
+#[get("/snippets/new")]
+fn snippet_editor(claims: Claims) -> Result<Template, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+
+    Ok(Template::render(
+        "control_panel/snippet_editor",
+        context! {},
+    ))
+}
+
+#[get("/snippets/edit/<id>")]
+async fn snippet_editor_id(
+    id: &str,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Template, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+
+    let snippet = Snippet::get_by_id(db, Uuid::from_str(id)?).await?;
+
+    Ok(Template::render(
+        "control_panel/snippet_editor",
+        context! { snippet, is_editing: true },
+    ))
+}
+
+/// With `recover`, the creator's autosaved working copy is loaded instead of an empty text.
+#[get("/editor?<recover>")]
+async fn editor(
+    recover: bool,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Template, Error> {
+    let autosave = Autosave::get(db, &claims.sub, None).await?;
+    let recovered = recover && autosave.is_some();
+    let text = match (recover, autosave.clone()) {
+        (true, Some(autosave)) => Some(autosave.recover_into(Text::default())),
+        _ => None,
+    };
+
+    Ok(Template::render(
         "control_panel/editor",
-        context! { is_publisher: claims.data.is_publisher() },
-    )
+        context! { text, is_publisher: claims.data.is_publisher(), autosave, recovered },
+    ))
 }
 
-#[get("/edit/<text_id>")]
+/// With `recover`, the creator's autosaved working copy is loaded instead of the saved text.
+#[get("/edit/<text_id>?<recover>")]
 async fn editor_text_id(
     text_id: i32,
+    recover: bool,
     db: &State<DatabaseHandler>,
+    presence: &State<Presence>,
     claims: Claims,
 ) -> Result<Template, Error> {
-    let text = Text::get_by_id(db, text_id, false).await?;
+    let saved_text = Text::get_by_id(db, text_id, false).await?;
+    let autosave = Autosave::get(db, &claims.sub, Some(text_id)).await?;
+    let recovered = recover && autosave.is_some();
+    let text = match (recover, autosave.clone()) {
+        (true, Some(autosave)) => autosave.recover_into(saved_text),
+        _ => saved_text,
+    };
+    presence.touch(text_id, &claims.sub, &claims.data.display_name);
+    let other_editors = presence.others(text_id, &claims.sub);
+    let available_transitions = text
+        .status
+        .available_transitions(&claims.data, text.is_author(&claims.sub));
+    let can_save_text = claims.data.is_publisher()
+        || (text.is_author(&claims.sub) && text.status.is_editable_by_author());
+    // Whoever may save the text may also move it to the trash.
+    let can_delete_text = can_save_text;
+    let status_history = Text::get_status_history(db, text_id).await?;
+    let can_comment = claims.data.is_publisher() || text.is_author(&claims.sub);
+    let review_comments = match can_comment {
+        true => ReviewComment::get_by_text_id(db, text_id).await?,
+        false => Vec::new(),
+    };
+    // The same people who may comment may share preview links.
+    let preview_links = match can_comment {
+        true => with_preview_urls(PreviewLink::get_active_by_text_id(db, text_id).await?)?,
+        false => Vec::new(),
+    };
 
     Ok(Template::render(
         "control_panel/editor",
-        context! { text, is_publisher: claims.data.is_publisher(), is_editing: true, creator: claims.data },
+        context! { text, is_publisher: claims.data.is_publisher(), is_editing: true, creator: claims.data, available_transitions, can_save_text, can_delete_text, status_history, can_comment, review_comments, preview_links, preview_link_max_days: PREVIEW_LINK_MAX_DAYS, other_editors, autosave, recovered },
+    ))
+}
+
+#[get("/revisions/<text_id>?<from>&<to>")]
+async fn text_revisions(
+    text_id: i32,
+    from: Option<i32>,
+    to: Option<i32>,
+    db: &State<DatabaseHandler>,
+    claims: Claims,
+) -> Result<Template, Error> {
+    let text = Text::get_by_id(db, text_id, false).await?;
+
+    if !text.is_author(&claims.sub) && !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Must be owner of text or publisher to view revisions!",
+            Status::Unauthorized,
+        ));
+    }
+
+    let revisions = TextRevision::get_by_text_id(db, text_id).await?;
+
+    // Compare two revisions if both are selected.
+    let (from_revision, to_revision) = match (from, to) {
+        (Some(from), Some(to)) => (
+            revisions.iter().find(|revision| revision.id == from),
+            revisions.iter().find(|revision| revision.id == to),
+        ),
+        _ => (None, None),
+    };
+
+    let mut diff = Vec::new();
+    if let (Some(from_revision), Some(to_revision)) = (from_revision, to_revision) {
+        let block_diffs =
+            TextRevision::diff_blocks(&from_revision.text_body, &to_revision.text_body);
+        let render_context = RenderContext::load(
+            db,
+            block_diffs.iter().map(|block_diff| &block_diff.block),
+            RenderTarget::Site,
+        )
+        .await?;
+        for block_diff in block_diffs {
+            let rendered_block = block_diff
+                .block
+                .render(&render_context)
+                .unwrap_or("INVALID BLOCK!".to_string());
+            diff.push(context! { kind: block_diff.kind, rendered_block });
+        }
+    }
+
+    Ok(Template::render(
+        "control_panel/revisions",
+        context! { creator: &claims.data, text, revisions: &revisions, from_revision, to_revision, diff, is_publisher: claims.data.is_publisher() },
     ))
 }
 
+#[get("/trash")]
+async fn trash(claims: Claims, db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+    let trashed_items = TrashedItem::get_all(db).await?;
+
+    Ok(Template::render(
+        "control_panel/trash",
+        context! { creator: &claims.data, trashed_items, retention_days: TrashedItem::retention_days() },
+    ))
+}
+
+/// Pairs every link with its token, for use in the `preview_links::list` macro.
+fn with_preview_urls(links: Vec<PreviewLink>) -> Result<Vec<impl Serialize>, Error> {
+    links
+        .into_iter()
+        .map(|link| {
+            let token = link.token()?;
+            Ok(context! { link, token })
+        })
+        .collect()
+}
+
+#[get("/preview-links")]
+async fn preview_links(claims: Claims, db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+    let preview_links = with_preview_urls(PreviewLink::get_active(db).await?)?;
+
+    Ok(Template::render(
+        "control_panel/preview_links",
+        context! { creator: &claims.data, preview_links },
+    ))
+}
+
+/// Pairs every option of a poll with its number of votes and share of all votes, in percent.
+fn poll_results(poll: &StoredPoll, votes: &[i64]) -> Vec<(String, i64, i64)> {
+    let total = votes.iter().sum::<i64>();
+    poll.poll
+        .option_list()
+        .into_iter()
+        .zip(votes)
+        .map(|(option, &votes)| {
+            let percent = if total > 0 { votes * 100 / total } else { 0 };
+            (option.to_string(), votes, percent)
+        })
+        .collect()
+}
+
+#[get("/polls")]
+async fn polls(claims: Claims, db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+
+    let mut polls = Vec::new();
+    for poll in StoredPoll::get_all(db).await? {
+        let results = poll_results(&poll, &poll.results(db).await?);
+        polls.push(context! { poll, results });
+    }
+
+    Ok(Template::render(
+        "control_panel/polls",
+        context! { creator: &claims.data, polls },
+    ))
+}
+
+/// The results of a poll as a CSV file.
+#[derive(Debug, Responder)]
+#[response(content_type = "text/csv")]
+struct PollExport {
+    csv: String,
+    content_disposition: Header<'static>,
+}
+
+#[get("/polls/<id>/export")]
+async fn poll_export(
+    claims: Claims,
+    db: &State<DatabaseHandler>,
+    id: &str,
+) -> Result<PollExport, Error> {
+    if !claims.admin {
+        return Err(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "You need to be an admin to access this view!",
+            Status::Unauthorized,
+        ));
+    };
+    let id = Uuid::from_str(id)?;
+    let poll = StoredPoll::get_by_id(db, id).await?.ok_or(Error::create(
+        &format!("{}:{}", file!(), line!()),
+        "There is no such poll!",
+        Status::NotFound,
+    ))?;
+
+    let quote = |value: &str| format!("\"{}\"", value.replace('"', "\"\""));
+    let mut csv = format!(
+        "{};{};{}\r\n",
+        quote("Alternativ"),
+        quote("Röster"),
+        quote("Andel (%)")
+    );
+    for (option, votes, percent) in poll_results(&poll, &poll.results(db).await?) {
+        csv.push_str(&format!("{};{};{}\r\n", quote(&option), votes, percent));
+    }
+
+    Ok(PollExport {
+        csv,
+        content_disposition: Header::new(
+            "Content-Disposition",
+            format!("attachment; filename=\"omrostning-{}.csv\"", id),
+        ),
+    })
+}
+
 /// These should be mounted on `/control-panel`!
 pub fn get_all_routes() -> Vec<Route> {
     routes![
         control_panel,
         login_page,
         image_gallery,
+        audio_archive,
         account_manager,
         page_manager,
         page_editor,
         page_editor_path,
-        preview_done_unpublished,
+        snippet_manager,
+        snippet_editor,
+        snippet_editor_id,
+        review_queue,
         editor,
         editor_text_id,
+        text_revisions,
+        preview_links,
+        polls,
+        poll_export,
+        trash,
     ]
 }
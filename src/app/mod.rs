@@ -1,14 +1,27 @@
 use crate::{
     anyresponder::AnyResponder,
+    block_editor::{
+        Block,
+        audio::Audio as AudioBlock,
+        render::{RenderTarget, render_bodies, render_body},
+        table_of_contents::table_of_contents,
+    },
     database::{
         DatabaseHandler,
-        models::{article::Text, creator::Creator},
+        models::{
+            article::Text, audio::Audio, correction::Correction, creator::Creator,
+            preview_link::PreviewLink, text_status::TextStatus,
+        },
     },
+    defaults::{AUDIO_FORMATS, PODCAST_FEED_MAX_EPISODES},
     error::Error,
+    ranged_file::RangedFile,
     token::Claims,
 };
-use rocket::{Route, State, response::Redirect};
+use rocket::{Route, State, http::ContentType, response::Redirect};
 use rocket_dyn_templates::{Template, context};
+use std::str::FromStr;
+use uuid::Uuid;
 
 pub mod control_panel;
 pub mod texts;
@@ -60,73 +73,170 @@ async fn text_by_id(
     }
 
     // Render all the blocks in the article body.
-    let mut rendered_blocks: Vec<String> = Vec::new();
-    for block in text.text_body.iter() {
-        rendered_blocks.push(
-            block
-                .render(db)
-                .await // FIXME: A non-mutable solution might be more elegant, but this await keyword might make that difficult.
-                .unwrap_or("INVALID BLOCK!".to_string()),
-        );
-    }
+    let rendered_blocks = render_body(db, &text.text_body, RenderTarget::Site).await?;
+    let table_of_contents = table_of_contents(&text.text_body);
 
-    // Bellow follows some bools used in the template to show different options/buttons.
-    // FIXME: Maybe these can be generated in a more elegant way? E.g. as a struct generated from the claims?
+    // Bellow follows what the logged in creator may do with the text, used in the template to show different options/buttons.
     let can_edit_text = match &claims {
         Some(claims) => {
-            claims.data.is_publisher() || (claims.sub == text.author && !text.is_published)
+            claims.data.is_publisher()
+                || (text.is_author(&claims.sub) && text.status.is_editable_by_author())
         }
         None => false,
     };
-    let can_mark_as_done = match &claims {
-        Some(claims) => !text.marked_as_done && claims.sub == text.author,
-        None => false,
-    };
-    let can_unmark_as_done = match &claims {
-        Some(claims) => !text.is_published && text.marked_as_done && claims.sub == text.author,
-        None => false,
-    };
-    let can_publish_text = match &claims {
-        Some(claims) => claims.data.is_publisher() && !text.is_published,
-        None => false,
+    let available_transitions = match &claims {
+        Some(claims) => text
+            .status
+            .available_transitions(&claims.data, text.is_author(&claims.sub)),
+        None => Vec::new(),
     };
-    let can_unpublish_text = match &claims {
-        Some(claims) => claims.data.is_publisher() && text.is_published,
+    let can_publish_text = available_transitions.contains(&TextStatus::Published);
+    let can_correct_text = match &claims {
+        Some(claims) => claims.data.is_publisher() && text.status == TextStatus::Published,
         None => false,
     };
 
+    let corrections = Correction::get_by_text_id(db, text.id).await?;
+
     let template = Template::render(
         "single-text-view",
-        context! { text, rendered_blocks: rendered_blocks.join(""), tags, authors, is_logged_in, can_edit_text, can_mark_as_done, can_unmark_as_done, can_publish_text, can_unpublish_text },
+        context! { text, rendered_blocks, table_of_contents, tags, authors, is_logged_in, can_edit_text, available_transitions, can_publish_text, can_correct_text, corrections },
     );
     Ok(AnyResponder::from(template))
 }
 
+/// Shows a text to anyone holding a valid preview link, even if the text is not published.
+#[get("/preview/<token>")]
+async fn text_preview(token: &str, db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    let tags = Text::get_all_tags(db, None).await?;
+    let authors = Creator::get_all_authors(db).await?;
+
+    let text = PreviewLink::get_text_by_token(db, token).await?;
+
+    let rendered_blocks = render_body(db, &text.text_body, RenderTarget::Site).await?;
+    let table_of_contents = table_of_contents(&text.text_body);
+
+    let corrections = Correction::get_by_text_id(db, text.id).await?;
+
+    Ok(Template::render(
+        "single-text-view",
+        context! { text, rendered_blocks, table_of_contents, tags, authors, corrections, is_logged_in: false, is_preview: true },
+    ))
+}
+
 #[get("/feed/atom.xml")]
 async fn feed_atom(db: &State<DatabaseHandler>) -> Result<Template, Error> {
     let texts = Text::get_n_latest(db, 50, true).await?;
-    let mut all_rendered_blocks: Vec<String> = Vec::new();
-
-    for text in texts.iter() {
-        let mut rendered_blocks: Vec<String> = Vec::new();
-        for block in text.text_body.iter() {
-            rendered_blocks.push(
-                block
-                    .render(db)
-                    .await // FIXME: A non-mutable solution might be more elegant, but this await keyword might make that difficult.
-                    .unwrap_or("INVALID BLOCK!".to_string()),
-            );
-        }
-        all_rendered_blocks.push(rendered_blocks.join(""));
-    }
+    // All texts are rendered together, so that the feed takes the same few queries however many blocks there are.
+    let all_rendered_blocks = render_bodies(
+        db,
+        &texts
+            .iter()
+            .map(|text| text.text_body.as_slice())
+            .collect::<Vec<&[Block]>>(),
+        RenderTarget::Feed,
+    )
+    .await?;
+    let all_corrections =
+        Correction::get_by_text_ids(db, &texts.iter().map(|text| text.id).collect::<Vec<i32>>())
+            .await?;
 
     Ok(Template::render(
         "atom",
-        context! { all_rendered_blocks, texts },
+        context! { all_rendered_blocks, all_corrections, texts },
+    ))
+}
+
+/// A podcast feed of the published texts with audio, with the first `Audio` block of each text as its episode.
+#[get("/feed/podcast.xml")]
+async fn feed_podcast(db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    let texts = Text::get_n_latest_with_audio(db, PODCAST_FEED_MAX_EPISODES).await?;
+    let episode_ids = texts
+        .iter()
+        .map(|text| {
+            text.text_body
+                .iter()
+                .find_map(|block| block.downcast_ref::<AudioBlock>())
+                .and_then(AudioBlock::uuid)
+        })
+        .collect::<Vec<Option<Uuid>>>();
+    let audio = Audio::get_by_ids(
+        db,
+        &episode_ids.iter().flatten().copied().collect::<Vec<Uuid>>(),
+    )
+    .await?;
+
+    // Texts whose audio has been trashed are left out, since an episode without audio is of no use to podcast apps.
+    let (texts, episodes): (Vec<Text>, Vec<Audio>) = texts
+        .into_iter()
+        .zip(episode_ids)
+        .filter_map(|(text, id)| {
+            let episode = audio.iter().find(|audio| Some(audio.id) == id)?;
+            Some((text, episode.clone()))
+        })
+        .unzip();
+    let audio_urls = episodes.iter().map(Audio::url).collect::<Vec<String>>();
+
+    let all_rendered_blocks = render_bodies(
+        db,
+        &texts
+            .iter()
+            .map(|text| text.text_body.as_slice())
+            .collect::<Vec<&[Block]>>(),
+        RenderTarget::Feed,
+    )
+    .await?;
+
+    Ok(Template::render(
+        "podcast",
+        context! { all_rendered_blocks, texts, episodes, audio_urls },
+    ))
+}
+
+/// Serves uploaded audio files, see `Audio::url`.
+/// Unlike the other uploaded files, these are served with support for range requests, so that players can seek.
+/// Files of trashed audio are not served, even though they are kept until the audio is purged.
+#[get("/dynamic-data/audio/<file>")]
+async fn audio_file(db: &State<DatabaseHandler>, file: &str) -> Option<RangedFile> {
+    let (id, extension) = file.split_once('.')?;
+    let id = Uuid::from_str(id).ok()?;
+    if Audio::get_by_ids(db, &[id]).await.ok()?.is_empty() {
+        return None;
+    }
+    let (_, mime_type, extension) = AUDIO_FORMATS
+        .iter()
+        .find(|(_, _, known)| *known == extension)?;
+
+    RangedFile::open(
+        Audio::path(id, extension),
+        ContentType::parse_flexible(mime_type)?,
+    )
+    .await
+}
+
+#[get("/corrections")]
+async fn corrections(db: &State<DatabaseHandler>) -> Result<Template, Error> {
+    let tags = Text::get_all_tags(db, None).await?;
+    let authors = Creator::get_all_authors(db).await?;
+
+    let corrections = Correction::get_all_published(db).await?;
+
+    Ok(Template::render(
+        "corrections",
+        context! { corrections, tags, authors },
     ))
 }
 
 /// This should be mounted on `/`!
 pub fn get_all_routes() -> Vec<Route> {
-    routes![landing, search, text_by_id, feed_atom]
+    routes![
+        landing,
+        search,
+        text_by_id,
+        text_preview,
+        feed_atom,
+        feed_podcast,
+        audio_file,
+        corrections
+    ]
 }
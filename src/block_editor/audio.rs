@@ -0,0 +1,122 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    database::{DatabaseHandler, models::audio::Audio as AudioData},
+    defaults::BLOCK_SHORT_FIELD_MAX_CHARS,
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, html, markdown,
+    render::{RenderContext, RenderNeeds},
+    validation::FieldErrors,
+};
+
+/// An audio file from the audio archive, played with the browser's own player.
+/// The first `Audio` block of a published text makes it an episode in the podcast feed.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Audio {
+    pub id: String,
+    pub caption: String,
+}
+
+impl Audio {
+    pub fn uuid(&self) -> Option<Uuid> {
+        Uuid::from_str(self.id.trim()).ok()
+    }
+}
+
+#[rocket::async_trait]
+impl BlockKind for Audio {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Audio",
+            label: "Ljud",
+            fields: vec![
+                BlockField {
+                    name: "id",
+                    label: Some("Ljudfilens ID (från ljudarkivet):"),
+                    placeholder: "Skriv ljudfilens id här",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "caption",
+                    label: Some("Beskrivning:"),
+                    placeholder: "Lyssna på intervjun...",
+                    input: FieldInput::Text,
+                },
+            ],
+        }
+    }
+
+    fn needs(&self, needs: &mut RenderNeeds) {
+        if let Some(id) = self.uuid() {
+            needs.audio(id);
+        }
+    }
+
+    /// Renders nothing if the audio has been trashed or removed, so that readers never see a broken block.
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        let Some(audio) = self.uuid().and_then(|id| context.audio(id)) else {
+            return Ok(String::new());
+        };
+
+        Ok(format!(
+            r#"<figure class="audio-block"><audio controls preload="metadata" src="{url}"><a href="{url}">Ladda ner ljudfilen</a></audio><figcaption class="caption">{} <span>{} ({})</span></figcaption></figure>"#,
+            html::escape(self.caption.trim()),
+            html::escape(&audio.title),
+            audio.formatted_duration(),
+            url = context.url(&audio.url()),
+        ))
+    }
+
+    async fn validate(&self, db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors.max_length("caption", &self.caption, BLOCK_SHORT_FIELD_MAX_CHARS);
+
+        match self.uuid() {
+            Some(id) => {
+                if AudioData::get_by_ids(db, &[id])
+                    .await
+                    .map(|audio| audio.is_empty())
+                    .unwrap_or(true)
+                {
+                    errors.add("id", "Det finns ingen ljudfil med det ID:t.");
+                }
+            }
+            None => {
+                errors.add("id", "Ljudfilens ID är ogiltigt.");
+            }
+        }
+    }
+
+    async fn warn(&self, db: &DatabaseHandler, warnings: &mut FieldErrors) {
+        let Some(id) = self.uuid() else {
+            return;
+        };
+
+        if AudioData::get_by_ids(db, &[id])
+            .await
+            .map(|audio| audio.is_empty())
+            .unwrap_or(true)
+        {
+            warnings.add(
+                "id",
+                &format!(
+                    "Det finns ingen ljudfil med ID {} (den kan ha raderats), så blocket visas inte för läsarna.",
+                    id
+                ),
+            );
+        }
+    }
+
+    fn plain_text(&self) -> String {
+        self.caption.clone()
+    }
+
+    fn markdown(&self) -> String {
+        format!("*Ljud: {}*", markdown::escape(self.caption.trim()))
+    }
+}
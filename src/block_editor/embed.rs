@@ -0,0 +1,362 @@
+use std::{env, sync::LazyLock};
+
+use regex::{Captures, Regex};
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, defaults::BLOCK_SHORT_FIELD_MAX_CHARS, error::Error};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, html, markdown,
+    render::{RenderContext, RenderTarget},
+    validation::FieldErrors,
+};
+
+/// What kind of content a provider embeds, which decides the size of the embed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbedFormat {
+    Video,
+    Audio,
+    Post,
+    Map,
+}
+
+impl EmbedFormat {
+    /// The CSS class of embeds of this format.
+    fn class(self) -> &'static str {
+        match self {
+            Self::Video => "video",
+            Self::Audio => "audio",
+            Self::Post => "post",
+            Self::Map => "map",
+        }
+    }
+}
+
+/// A site whose content can be embedded, e.g. YouTube.
+pub struct EmbedProvider {
+    /// Shown to readers, e.g. in "Visa innehåll från YouTube".
+    pub name: &'static str,
+    pub format: EmbedFormat,
+    /// Matches the links to the provider that can be embedded.
+    pattern: Regex,
+    /// Makes the address of the iframe from the captures of `pattern`.
+    embed_url: fn(&Captures) -> String,
+    /// Whether a link that matches `pattern` may be embedded, e.g. whether its site is trusted.
+    is_allowed: fn(&Captures) -> bool,
+}
+
+impl EmbedProvider {
+    fn new(
+        name: &'static str,
+        format: EmbedFormat,
+        pattern: &str,
+        embed_url: fn(&Captures) -> String,
+    ) -> Self {
+        Self {
+            name,
+            format,
+            pattern: Regex::new(pattern).expect("Invalid embed provider pattern!"),
+            embed_url,
+            is_allowed: |_| true,
+        }
+    }
+
+    /// Only embeds the links that `is_allowed` accepts.
+    fn only_if(self, is_allowed: fn(&Captures) -> bool) -> Self {
+        Self { is_allowed, ..self }
+    }
+
+    /// Gets the address of the iframe for `link`, if it is a link to this provider.
+    pub fn embed_url(&self, link: &str) -> Option<String> {
+        self.pattern
+            .captures(link.trim())
+            .filter(|captures| (self.is_allowed)(captures))
+            .map(|captures| (self.embed_url)(&captures))
+    }
+
+    /// Finds the provider of `link`, and the address of the iframe for it.
+    pub fn find(link: &str) -> Option<(&'static Self, String)> {
+        PROVIDERS
+            .iter()
+            .find_map(|provider| Some((provider, provider.embed_url(link)?)))
+    }
+}
+
+/// All the providers that can be embedded. Only the captures the patterns allow are put in the iframe addresses,
+/// so every capture must be limited to characters that are safe in a URL.
+static PROVIDERS: LazyLock<Vec<EmbedProvider>> = LazyLock::new(|| {
+    vec![
+        // The privacy-enhanced mode of YouTube, that does not set cookies before the video is played.
+        EmbedProvider::new(
+            "YouTube",
+            EmbedFormat::Video,
+            r"^(https?://)?((www|m)\.)?(youtube(-nocookie)?\.com/(watch\?v=|shorts/|live/|embed/)|youtu\.be/)(?<id>[A-Za-z0-9_-]{11})([?&#].*)?$",
+            |captures| format!("https://www.youtube-nocookie.com/embed/{}", &captures["id"]),
+        ),
+        EmbedProvider::new(
+            "Vimeo",
+            EmbedFormat::Video,
+            r"^(https?://)?((www|player)\.)?vimeo\.com/(video/)?(?<id>[0-9]+)/?([?#].*)?$",
+            |captures| format!("https://player.vimeo.com/video/{}?dnt=1", &captures["id"]),
+        ),
+        EmbedProvider::new(
+            "Spotify",
+            EmbedFormat::Audio,
+            r"^(https?://)?open\.spotify\.com/(intl-[a-z-]+/)?(embed/)?(?<kind>track|album|playlist|episode|show|artist)/(?<id>[A-Za-z0-9]+)/?([?#].*)?$",
+            |captures| {
+                format!(
+                    "https://open.spotify.com/embed/{}/{}",
+                    &captures["kind"], &captures["id"]
+                )
+            },
+        ),
+        EmbedProvider::new(
+            "SoundCloud",
+            EmbedFormat::Audio,
+            r"^(https?://)?(www\.|m\.)?soundcloud\.com/(?<user>[A-Za-z0-9_-]+)/(?<track>[A-Za-z0-9_-]+(/[A-Za-z0-9_-]+)?)/?([?#].*)?$",
+            |captures| {
+                format!(
+                    "https://w.soundcloud.com/player/?url=https%3A%2F%2Fsoundcloud.com%2F{}%2F{}",
+                    &captures["user"],
+                    captures["track"].replace('/', "%2F")
+                )
+            },
+        ),
+        EmbedProvider::new(
+            "Mastodon",
+            EmbedFormat::Post,
+            r"^https://(?<instance>[a-z0-9-]+(\.[a-z0-9-]+)+)/@(?<user>[A-Za-z0-9_]+)/(?<id>[0-9]+)/?([?#].*)?$",
+            |captures| {
+                format!(
+                    "https://{}/@{}/{}/embed",
+                    &captures["instance"], &captures["user"], &captures["id"]
+                )
+            },
+        )
+        // Anyone can run an instance, so only the ones in the comma separated `MASTODON_INSTANCES`
+        // environment variable are embedded, e.g. `mastodon.social,mastodon.nu`.
+        .only_if(|captures| {
+            is_listed_instance(
+                &captures["instance"],
+                &env::var("MASTODON_INSTANCES").unwrap_or_default(),
+            )
+        }),
+        EmbedProvider::new(
+            "Google Maps",
+            EmbedFormat::Map,
+            r"^(https?://)?(www\.)?google\.[a-z]{2,3}(\.[a-z]{2})?/maps/(embed\?pb=(?<pb>[A-Za-z0-9!%._-]+)|place/(?<place>[A-Za-z0-9%+,._-]+)|@(?<coordinates>-?[0-9]+\.[0-9]+,-?[0-9]+\.[0-9]+),(?<zoom>[0-9]+)(\.[0-9]+)?z)([/?#].*)?$",
+            |captures| {
+                if let Some(pb) = captures.name("pb") {
+                    format!("https://www.google.com/maps/embed?pb={}", pb.as_str())
+                } else if let Some(place) = captures.name("place") {
+                    format!(
+                        "https://www.google.com/maps?q={}&output=embed",
+                        place.as_str()
+                    )
+                } else {
+                    format!(
+                        "https://www.google.com/maps?q={}&z={}&output=embed",
+                        &captures["coordinates"], &captures["zoom"]
+                    )
+                }
+            },
+        ),
+    ]
+});
+
+/// Whether `instance` is one of the comma separated host names in `instances`.
+fn is_listed_instance(instance: &str, instances: &str) -> bool {
+    instances
+        .split(',')
+        .map(str::trim)
+        .any(|listed| !listed.is_empty() && listed.eq_ignore_ascii_case(instance))
+}
+
+/// The names of all providers, e.g. for error messages.
+fn provider_names() -> String {
+    PROVIDERS
+        .iter()
+        .map(|provider| provider.name)
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+/// Embeds content from another site, e.g. a video or a map.
+/// Nothing is loaded from the other site until the reader chooses to show the content.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Embed {
+    pub link: String,
+    pub caption: Option<String>,
+}
+
+#[rocket::async_trait]
+impl BlockKind for Embed {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Embed",
+            label: "Inbäddat innehåll (video, ljud, karta...)",
+            fields: vec![
+                BlockField {
+                    name: "link",
+                    label: Some("Länk till innehållet:"),
+                    placeholder: "Klistra in en länk från t.ex. YouTube, Vimeo eller Spotify",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "caption",
+                    label: Some("Bildtext:"),
+                    placeholder: "Skriv bildtext här...",
+                    input: FieldInput::Text,
+                },
+            ],
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        let (provider, embed_url) = EmbedProvider::find(&self.link).ok_or(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "Unsupported embed link!",
+            Status::BadRequest,
+        ))?;
+
+        let link = self.link.trim();
+        let link = if link.starts_with("https://") || link.starts_with("http://") {
+            link.to_string()
+        } else {
+            format!("https://{}", link)
+        };
+        let caption = self.caption.as_deref().unwrap_or_default();
+
+        // Feed readers do not show iframes, so the feed links to the content instead.
+        if context.target() == RenderTarget::Feed {
+            return Ok(format!(
+                r#"<p><a href="{}">{}</a></p>"#,
+                html::escape(&link),
+                html::escape(&if caption.is_empty() {
+                    format!("Visa innehåll från {}", provider.name)
+                } else {
+                    format!("{} ({})", caption, provider.name)
+                }),
+            ));
+        }
+
+        // The iframe is kept in a `<template>` until the reader clicks the button, see `static/scripts/embed.js`.
+        Ok(format!(
+            r#"<figure class="embed {}"><div class="embed-consent"><p>Här finns innehåll från {name}. Om du visar det kan {name} spara uppgifter om dig.</p><button type="button" class="btn">Visa innehåll från {name}</button><a href="{}" target="_blank" rel="noopener noreferrer">Öppna hos {name}</a><template><iframe src="{}" title="{}" loading="lazy" allow="encrypted-media; fullscreen; picture-in-picture" allowfullscreen referrerpolicy="strict-origin-when-cross-origin"></iframe></template></div>{}</figure>"#,
+            provider.format.class(),
+            html::escape(&link),
+            html::escape(&embed_url),
+            html::escape(if caption.is_empty() {
+                provider.name
+            } else {
+                caption
+            }),
+            if caption.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    r#"<figcaption class="caption">{}</figcaption>"#,
+                    html::escape(caption)
+                )
+            },
+            name = html::escape(provider.name),
+        ))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        if EmbedProvider::find(&self.link).is_none() {
+            errors.add(
+                "link",
+                &format!(
+                    "Länken kan inte bäddas in. Det går att bädda in länkar från {}.",
+                    provider_names()
+                ),
+            );
+        }
+
+        if let Some(caption) = &self.caption {
+            errors.max_length("caption", caption, BLOCK_SHORT_FIELD_MAX_CHARS);
+        }
+    }
+
+    fn plain_text(&self) -> String {
+        self.caption.clone().unwrap_or_default()
+    }
+
+    fn markdown(&self) -> String {
+        let link = self.link.trim();
+        format!(
+            "[{}](<{}>)",
+            markdown::escape(match self.caption.as_deref().map(str::trim) {
+                Some(caption) if !caption.is_empty() => caption,
+                _ => link,
+            }),
+            link.replace(['<', '>'], "")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that links to every provider are embedded with the right address, and that other links are not.
+    #[test]
+    fn test_find_provider() {
+        let embed_url = |link: &str| EmbedProvider::find(link).map(|(_, embed_url)| embed_url);
+
+        for link in [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?v=dQw4w9WgXcQ&t=42",
+            "youtu.be/dQw4w9WgXcQ",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(
+                embed_url(link).as_deref(),
+                Some("https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ")
+            );
+        }
+
+        assert_eq!(
+            embed_url("https://vimeo.com/76979871").as_deref(),
+            Some("https://player.vimeo.com/video/76979871?dnt=1")
+        );
+        assert_eq!(
+            embed_url("https://open.spotify.com/intl-sv/track/4uLU6hMCjMI75M1A2tKUQC?si=1")
+                .as_deref(),
+            Some("https://open.spotify.com/embed/track/4uLU6hMCjMI75M1A2tKUQC")
+        );
+        assert_eq!(
+            embed_url("https://soundcloud.com/artist/some-track").as_deref(),
+            Some(
+                "https://w.soundcloud.com/player/?url=https%3A%2F%2Fsoundcloud.com%2Fartist%2Fsome-track"
+            )
+        );
+        assert_eq!(
+            embed_url("https://www.google.com/maps/@59.3293,18.0686,12z").as_deref(),
+            Some("https://www.google.com/maps?q=59.3293,18.0686&z=12&output=embed")
+        );
+
+        assert_eq!(embed_url("https://example.com/watch?v=dQw4w9WgXcQ"), None);
+        assert_eq!(embed_url("https://youtu.be/kort"), None);
+        assert_eq!(embed_url(r#"https://vimeo.com/1"><script>"#), None);
+    }
+
+    /// Tests that only the Mastodon instances that are listed are embedded.
+    #[test]
+    fn test_mastodon_instances() {
+        assert_eq!(
+            EmbedProvider::find("https://evil.example/@user/1").map(|(_, embed_url)| embed_url),
+            None
+        );
+
+        assert!(is_listed_instance(
+            "mastodon.social",
+            "mastodon.nu, Mastodon.Social"
+        ));
+        assert!(!is_listed_instance("evil.example", "mastodon.social"));
+        assert!(!is_listed_instance("evil.example", ""));
+        assert!(!is_listed_instance("", " , "));
+    }
+}
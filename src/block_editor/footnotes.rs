@@ -0,0 +1,200 @@
+//! Footnotes that cite sources, written as `^[The source]` in the text of `Paragraph` and `TextBox` blocks.
+//! The markers are numbered in the order they appear in the text, and the sources are listed at its end.
+
+use super::{html, render::RenderContext, validation::FieldErrors};
+
+/// Stands in for a marker while the text around it is rendered as Markdown.
+/// These are private use characters, so they are removed from what creators write.
+const PLACEHOLDER_START: char = '\u{E000}';
+const PLACEHOLDER_END: char = '\u{E001}';
+
+/// Why the footnotes of a text could not be read.
+#[derive(Debug, PartialEq)]
+enum FootnoteError {
+    Unclosed,
+    Empty,
+}
+
+/// Replaces every `^[...]` in `text` with a placeholder holding the index of its source, and returns the sources.
+/// Brackets inside a source must be balanced, e.g. `^[[SCB](https://scb.se)]`, and `\^[` is not a marker.
+fn extract(text: &str) -> Result<(String, Vec<String>), FootnoteError> {
+    let text = text.replace([PLACEHOLDER_START, PLACEHOLDER_END], "");
+    let mut result = String::new();
+    let mut sources = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                result.push(c);
+                result.extend(chars.next());
+            }
+            '^' if chars.peek() == Some(&'[') => {
+                chars.next();
+                let mut source = String::new();
+                let mut depth = 0;
+                loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            source.push('\\');
+                            source.extend(chars.next());
+                        }
+                        Some('[') => {
+                            depth += 1;
+                            source.push('[');
+                        }
+                        Some(']') if depth == 0 => break,
+                        Some(']') => {
+                            depth -= 1;
+                            source.push(']');
+                        }
+                        Some(c) => source.push(c),
+                        None => return Err(FootnoteError::Unclosed),
+                    }
+                }
+
+                if source.trim().is_empty() {
+                    return Err(FootnoteError::Empty);
+                }
+                result.push_str(&format!(
+                    "{}{}{}",
+                    PLACEHOLDER_START,
+                    sources.len(),
+                    PLACEHOLDER_END
+                ));
+                sources.push(source.trim().to_string());
+            }
+            c => result.push(c),
+        }
+    }
+
+    Ok((result, sources))
+}
+
+/// Renders Markdown with footnotes as sanitized HTML, numbering the footnotes in `context`.
+pub fn render_markdown(text: &str, context: &RenderContext) -> String {
+    let Ok((text, sources)) = extract(text) else {
+        return html::markdown(text);
+    };
+    let rendered = html::markdown(&text);
+
+    let mut result = String::new();
+    let mut rest = rendered.as_str();
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + PLACEHOLDER_START.len_utf8()..];
+        let Some(end) = rest.find(PLACEHOLDER_END) else {
+            break;
+        };
+
+        if let Some(source) = rest[..end]
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| sources.get(index))
+        {
+            let (number, is_first) = context.footnote(source);
+            result.push_str(&format!(
+                r##"<sup class="footnote-ref"><a href="#kalla-{number}"{}>{number}</a></sup>"##,
+                if is_first {
+                    format!(r#" id="kalla-ref-{}""#, number)
+                } else {
+                    String::new()
+                },
+            ));
+        }
+        rest = &rest[end + PLACEHOLDER_END.len_utf8()..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+/// The text without markup, with each source in parentheses where its footnote is, e.g. for search.
+pub fn plain_text(text: &str) -> String {
+    let Ok((text, sources)) = extract(text) else {
+        return html::plain_text(&html::markdown(text));
+    };
+
+    let mut plain_text = html::plain_text(&html::markdown(&text));
+    for (index, source) in sources.iter().enumerate() {
+        plain_text = plain_text.replace(
+            &format!("{}{}{}", PLACEHOLDER_START, index, PLACEHOLDER_END),
+            &format!(" ({})", html::plain_text(&html::markdown(source))),
+        );
+    }
+    plain_text
+}
+
+/// Renders the numbered list of sources at the end of a text, or nothing if it has no footnotes.
+pub fn render_sources(sources: &[String]) -> String {
+    if sources.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        r#"<section class="sources" aria-labelledby="kallor"><h2 id="kallor">Källor</h2><ol>{}</ol></section>"#,
+        sources
+            .iter()
+            .enumerate()
+            .map(|(i, source)| {
+                // A source is a single line, so it does not need to be a paragraph of its own.
+                let source = html::markdown(source);
+                let source = source.trim();
+                format!(
+                    r##"<li id="kalla-{number}">{} <a href="#kalla-ref-{number}" class="footnote-back" aria-label="Tillbaka till texten">↩</a></li>"##,
+                    source
+                        .strip_prefix("<p>")
+                        .and_then(|source| source.strip_suffix("</p>"))
+                        .unwrap_or(source),
+                    number = i + 1,
+                )
+            })
+            .collect::<String>()
+    )
+}
+
+/// Adds an error to `errors` if the footnotes of `text` cannot be read.
+pub fn validate(field: &'static str, text: &str, errors: &mut FieldErrors) {
+    match extract(text) {
+        Ok(_) => {}
+        Err(FootnoteError::Unclosed) => {
+            errors.add(
+                field,
+                "En källhänvisning saknar ett avslutande ]. Skriv källhänvisningar som ^[Källan].",
+            );
+        }
+        Err(FootnoteError::Empty) => {
+            errors.add(field, "En källhänvisning är tom.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that markers are found in order, also with links in them, and that broken ones are reported.
+    #[test]
+    fn test_extract() {
+        let (text, sources) =
+            extract("Ett påstående^[SCB, 2023] och ett till^[[Rapporten](https://example.com)]. Inte \\^[detta].")
+                .unwrap();
+        assert_eq!(
+            text,
+            "Ett påstående\u{E000}0\u{E001} och ett till\u{E000}1\u{E001}. Inte \\^[detta]."
+        );
+        assert_eq!(sources, ["SCB, 2023", "[Rapporten](https://example.com)"]);
+
+        assert_eq!(extract("Trasig^[källa"), Err(FootnoteError::Unclosed));
+        assert_eq!(extract("Tom^[ ]"), Err(FootnoteError::Empty));
+    }
+
+    /// Tests that the sources are kept in the plain text, without any Markdown.
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            plain_text("Ett *påstående*^[[SCB](https://scb.se), 2023] & mer."),
+            "Ett påstående (SCB, 2023) & mer."
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        DatabaseHandler,
+        models::image::{IMG_M_SIZE, IMG_S_SIZE, Image as ImageData},
+    },
+    defaults::BLOCK_SHORT_FIELD_MAX_CHARS,
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, SelectOption, html, markdown,
+    render::{RenderContext, RenderNeeds},
+    validation::FieldErrors,
+};
+
+/// How the images in a gallery are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum GalleryLayout {
+    /// All images at once, in a grid.
+    #[default]
+    Grid,
+    /// One image at a time, scrolled through sideways.
+    Slideshow,
+}
+
+/// An image in a gallery.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GalleryImage {
+    pub id: String,
+    pub caption: String,
+}
+
+/// Several images with their own captions, e.g. for a photo reportage.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Gallery {
+    pub images: Vec<GalleryImage>,
+    #[serde(default)]
+    pub layout: GalleryLayout,
+}
+
+/// Renders one image of a gallery as a `<figure>`; `label` is used by slideshows, e.g. "2 av 5".
+fn render_image(
+    context: &RenderContext,
+    image: &GalleryImage,
+    image_data: &ImageData,
+    label: Option<&str>,
+) -> String {
+    let images = context.url("/dynamic-data/images");
+    format!(
+        r#"<li><figure{}><a href="{images}/l/{id}.webp"><img src="{images}/m/{id}.webp" srcset="{images}/s/{id}.webp {}w, {images}/m/{id}.webp {}w" sizes="(max-width: {}px) 100vw, {}px" alt="{}" loading="lazy" /></a><figcaption class="caption">{} <span>Foto: {}.</span></figcaption></figure></li>"#,
+        label
+            .map(|label| format!(r#" aria-roledescription="bild" aria-label="{}""#, label))
+            .unwrap_or_default(),
+        IMG_S_SIZE,
+        IMG_M_SIZE,
+        IMG_M_SIZE,
+        IMG_M_SIZE,
+        html::escape(image_data.description.as_deref().unwrap_or_default()),
+        html::escape(&image.caption),
+        html::escape(&image_data.author),
+        id = image_data.id,
+    )
+}
+
+#[rocket::async_trait]
+impl BlockKind for Gallery {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Gallery",
+            label: "Bildgalleri / bildspel",
+            fields: vec![
+                BlockField {
+                    name: "images",
+                    label: Some("Bilder:"),
+                    placeholder: "",
+                    input: FieldInput::ImageList,
+                },
+                BlockField {
+                    name: "layout",
+                    label: Some("Visa som:"),
+                    placeholder: "",
+                    input: FieldInput::Select {
+                        options: vec![
+                            SelectOption {
+                                value: "Grid",
+                                label: "Rutnät",
+                            },
+                            SelectOption {
+                                value: "Slideshow",
+                                label: "Bildspel",
+                            },
+                        ],
+                    },
+                },
+            ],
+        }
+    }
+
+    fn needs(&self, needs: &mut RenderNeeds) {
+        for image in self.images.iter() {
+            if let Ok(id) = Uuid::from_str(image.id.trim()) {
+                needs.image(id);
+            }
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        // Keep the order of the gallery, and leave out images that have been removed since.
+        let images = self
+            .images
+            .iter()
+            .filter_map(|image| {
+                let id = Uuid::from_str(image.id.trim()).ok()?;
+                context.image(id).map(|image_data| (image, image_data))
+            })
+            .collect::<Vec<_>>();
+
+        let rendered_images = images
+            .iter()
+            .enumerate()
+            .map(|(i, (image, image_data))| match self.layout {
+                GalleryLayout::Grid => render_image(context, image, image_data, None),
+                GalleryLayout::Slideshow => render_image(
+                    context,
+                    image,
+                    image_data,
+                    Some(&format!("{} av {}", i + 1, images.len())),
+                ),
+            })
+            .collect::<String>();
+
+        Ok(match self.layout {
+            GalleryLayout::Grid => format!(
+                r#"<div class="gallery grid" role="group" aria-label="Bildgalleri"><ul>{}</ul></div>"#,
+                rendered_images
+            ),
+            GalleryLayout::Slideshow => format!(
+                r#"<section class="gallery slideshow" aria-roledescription="bildspel" aria-label="Bildspel med {} bilder"><ul tabindex="0">{}</ul></section>"#,
+                images.len(),
+                rendered_images
+            ),
+        })
+    }
+
+    async fn validate(&self, db: &DatabaseHandler, errors: &mut FieldErrors) {
+        if self.images.is_empty() {
+            errors.add("images", "Galleriet måste ha minst en bild.");
+        }
+
+        let ids = self
+            .images
+            .iter()
+            .filter_map(|image| Uuid::from_str(image.id.trim()).ok())
+            .collect::<Vec<Uuid>>();
+        let existing_ids = match ImageData::get_by_ids(db, &ids).await {
+            Ok(images) => images.into_iter().map(|image| image.id).collect(),
+            Err(_) => Vec::new(),
+        };
+
+        for (i, image) in self.images.iter().enumerate() {
+            match Uuid::from_str(image.id.trim()) {
+                Ok(id) if existing_ids.contains(&id) => {}
+                Ok(_) => {
+                    errors.add(
+                        "images",
+                        &format!("Bild {}: det finns ingen bild med det ID:t.", i + 1),
+                    );
+                }
+                Err(_) => {
+                    errors.add(
+                        "images",
+                        &format!("Bild {}: bildens ID är ogiltigt.", i + 1),
+                    );
+                }
+            }
+
+            if image.caption.chars().count() > BLOCK_SHORT_FIELD_MAX_CHARS {
+                errors.add(
+                    "images",
+                    &format!(
+                        "Bild {}: bildtexten får vara högst {} tecken lång.",
+                        i + 1,
+                        BLOCK_SHORT_FIELD_MAX_CHARS
+                    ),
+                );
+            }
+        }
+    }
+
+    fn plain_text(&self) -> String {
+        self.images
+            .iter()
+            .map(|image| image.caption.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    fn markdown(&self) -> String {
+        self.images
+            .iter()
+            .map(|image| {
+                format!(
+                    "![{}]({})",
+                    markdown::escape(&image.caption),
+                    markdown::image_url(image.id.trim())
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+}
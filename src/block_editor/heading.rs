@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, defaults::BLOCK_SHORT_FIELD_MAX_CHARS, error::Error};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, SelectOption, html, markdown,
+    render::RenderContext, validation::FieldErrors,
+};
+
+/// How important a heading is; `H2` is directly below the title of the text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum HeadingLevel {
+    #[default]
+    H2,
+    H3,
+    H4,
+}
+
+impl HeadingLevel {
+    /// 2 to 4, as in `<h2>` to `<h4>`.
+    pub fn number(self) -> u8 {
+        match self {
+            Self::H2 => 2,
+            Self::H3 => 3,
+            Self::H4 => 4,
+        }
+    }
+
+    /// The level of e.g. a Markdown heading, where level 1 is the title, so it becomes `H2` like level 2.
+    pub fn from_number(number: u8) -> Self {
+        match number {
+            ..=2 => Self::H2,
+            3 => Self::H3,
+            _ => Self::H4,
+        }
+    }
+}
+
+/// A heading with an anchor, so that it can be linked to, and listed in the table of contents.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Heading {
+    pub heading: String,
+    /// Headings stored before there were levels are `H2`.
+    #[serde(default)]
+    pub level: HeadingLevel,
+}
+
+#[rocket::async_trait]
+impl BlockKind for Heading {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Heading",
+            label: "Rubrik",
+            fields: vec![
+                BlockField {
+                    name: "heading",
+                    label: None,
+                    placeholder: "Skriv rubrik här...",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "level",
+                    label: Some("Nivå:"),
+                    placeholder: "",
+                    input: FieldInput::Select {
+                        options: vec![
+                            SelectOption {
+                                value: "H2",
+                                label: "Rubrik",
+                            },
+                            SelectOption {
+                                value: "H3",
+                                label: "Underrubrik",
+                            },
+                            SelectOption {
+                                value: "H4",
+                                label: "Underrubrik i underrubrik",
+                            },
+                        ],
+                    },
+                },
+            ],
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        Ok(format!(
+            r#"<h{level}{}>{}</h{level}>"#,
+            context
+                .anchor(&self.heading)
+                .map(|anchor| format!(r#" id="{}""#, anchor))
+                .unwrap_or_default(),
+            html::escape(&self.heading),
+            level = self.level.number(),
+        ))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors.required("heading", &self.heading).max_length(
+            "heading",
+            &self.heading,
+            BLOCK_SHORT_FIELD_MAX_CHARS,
+        );
+    }
+
+    fn plain_text(&self) -> String {
+        self.heading.clone()
+    }
+
+    fn markdown(&self) -> String {
+        format!(
+            "{} {}",
+            "#".repeat(self.level.number().into()),
+            markdown::escape(self.heading.trim())
+        )
+    }
+}
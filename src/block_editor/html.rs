@@ -0,0 +1,113 @@
+//! Everything blocks put into the HTML of the site goes through here,
+//! so that user input is always escaped or sanitized the same way.
+
+use std::{env, sync::LazyLock};
+
+use ammonia::Builder;
+use comrak::{Options, markdown_to_html};
+use rocket_dyn_templates::tera::escape_html;
+
+use crate::defaults::{RAW_HTML_ALLOWED_TAGS, RAW_HTML_TAG_ATTRIBUTES};
+
+/// The policy used for all HTML that creators produce, e.g. rendered markdown.
+static SANITIZER: LazyLock<Builder<'static>> = LazyLock::new(Builder::default);
+
+/// The policy used for `RawHtml` blocks, see `raw_html_sanitizer`.
+static RAW_HTML_SANITIZER: LazyLock<Builder<'static>> =
+    LazyLock::new(|| raw_html_sanitizer(env::var("RAW_HTML_ALLOWED_TAGS").ok()));
+
+/// Builds the policy for `RawHtml` blocks.
+/// Only the tags in `RAW_HTML_ALLOWED_TAGS` are kept, unless overridden by `allowed_tags`,
+/// the comma separated `RAW_HTML_ALLOWED_TAGS` environment variable.
+/// Tags that are always removed with their content, e.g. `script` and `style`, are never allowed,
+/// as ammonia panics if a tag is both.
+fn raw_html_sanitizer(allowed_tags: Option<String>) -> Builder<'static> {
+    let mut builder = Builder::default();
+    let clean_content_tags = builder.clone_clean_content_tags();
+
+    let allowed_tags = match allowed_tags {
+        Some(tags) => String::leak(tags)
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty() && !clean_content_tags.contains(tag))
+            .collect(),
+        None => RAW_HTML_ALLOWED_TAGS.iter().copied().collect(),
+    };
+
+    builder.tags(allowed_tags);
+    for (tag, attributes) in RAW_HTML_TAG_ATTRIBUTES {
+        builder.add_tag_attributes(tag, attributes.iter());
+    }
+    builder
+}
+
+/// Escapes text so that it can be put in HTML, both as content and as an attribute value.
+pub fn escape(text: &str) -> String {
+    escape_html(text)
+}
+
+/// Removes everything but safe HTML.
+pub fn sanitize(html: &str) -> String {
+    SANITIZER.clean(html).to_string()
+}
+
+/// Renders markdown as sanitized HTML.
+pub fn markdown(text: &str) -> String {
+    sanitize(&markdown_to_html(text, &Options::default()))
+}
+
+/// Removes all tags from HTML and keeps only the text, e.g. for search.
+pub fn plain_text(html: &str) -> String {
+    Builder::empty()
+        .clean(html)
+        .to_string()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// Removes everything but the allowed tags from the HTML of a `RawHtml` block.
+pub fn sanitize_raw_html(html: &str) -> String {
+    RAW_HTML_SANITIZER.clean(html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that nothing from creators can break out of the HTML it is put in.
+    #[test]
+    fn test_escape_and_sanitize() {
+        assert_eq!(
+            escape(r#""><script>alert(1)</script>"#),
+            "&quot;&gt;&lt;script&gt;alert(1)&lt;&#x2F;script&gt;"
+        );
+        assert_eq!(
+            markdown("*Hej* <script>alert(1)</script>"),
+            "<p><em>Hej</em> alert(1)</p>\n"
+        );
+        assert_eq!(
+            sanitize_raw_html(
+                r#"<p onclick="alert(1)">Hej</p><iframe src="https://example.com"></iframe><script>alert(1)</script>"#
+            ),
+            "<p>Hej</p>"
+        );
+    }
+
+    /// Tests that an overridden allowlist cannot let `script` or `style` through, nor make cleaning panic.
+    #[test]
+    fn test_raw_html_allowed_tags_override() {
+        let sanitizer = raw_html_sanitizer(Some("p, style,script".to_string()));
+        assert_eq!(
+            sanitizer
+                .clean("<p>Hej</p><style>p { color: red; }</style><script>alert(1)</script>")
+                .to_string(),
+            "<p>Hej</p>"
+        );
+    }
+}
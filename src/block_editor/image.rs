@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    database::{DatabaseHandler, models::image::Image as ImageData},
+    defaults::BLOCK_SHORT_FIELD_MAX_CHARS,
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, html, markdown,
+    render::{RenderContext, RenderNeeds},
+    validation::FieldErrors,
+};
+
+/// An image with a caption.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Image {
+    pub id: String,
+    pub caption: String,
+    pub image_data: Option<ImageData>,
+}
+
+#[rocket::async_trait]
+impl BlockKind for Image {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Image",
+            label: "Bild",
+            fields: vec![
+                BlockField {
+                    name: "id",
+                    label: Some("Bildens ID:"),
+                    placeholder: "Skriv bildens id här",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "caption",
+                    label: Some("Bildtext:"),
+                    placeholder: "Skriv bildtext här...",
+                    input: FieldInput::Text,
+                },
+            ],
+        }
+    }
+
+    fn needs(&self, needs: &mut RenderNeeds) {
+        if let Ok(image_id) = Uuid::from_str(self.id.trim()) {
+            needs.image(image_id);
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        let image_id = Uuid::from_str(self.id.trim())?;
+        let image_data = context.image(image_id).ok_or(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "There is no such image!",
+            Status::NotFound,
+        ))?;
+
+        Ok(format!(
+            r#"<img src="{}" alt="{}" /><p class="caption">{} <span>Foto: {}.</span></p>"#,
+            context.url(&format!("/dynamic-data/images/m/{}.webp", image_data.id)),
+            html::escape(image_data.description.as_deref().unwrap_or_default()),
+            html::escape(&self.caption),
+            html::escape(&image_data.author),
+        ))
+    }
+
+    async fn validate(&self, db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors.max_length("caption", &self.caption, BLOCK_SHORT_FIELD_MAX_CHARS);
+
+        match Uuid::from_str(self.id.trim()) {
+            Ok(image_id) => {
+                if ImageData::get_by_id(db, image_id).await.is_err() {
+                    errors.add("id", "Det finns ingen bild med det ID:t.");
+                }
+            }
+            Err(_) => {
+                errors.add("id", "Bildens ID är ogiltigt.");
+            }
+        }
+    }
+
+    fn plain_text(&self) -> String {
+        self.caption.clone()
+    }
+
+    fn markdown(&self) -> String {
+        format!(
+            "![{}]({})",
+            markdown::escape(&self.caption),
+            markdown::image_url(self.id.trim())
+        )
+    }
+}
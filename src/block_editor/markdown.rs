@@ -0,0 +1,242 @@
+//! Converts between Markdown documents and blocks, so that texts can be written in other editors.
+
+use std::{env, str::FromStr, sync::LazyLock};
+
+use comrak::{
+    Arena, Options, escape_commonmark_inline, escape_commonmark_link_destination,
+    format_commonmark,
+    nodes::{Node, NodeValue},
+    parse_document,
+};
+use regex::Regex;
+use uuid::Uuid;
+
+use crate::{
+    database::{DatabaseHandler, models::image::Image as ImageData},
+    error::Error,
+};
+
+use super::{
+    Block,
+    heading::{Heading, HeadingLevel},
+    image::Image,
+    paragraph::Paragraph,
+    quote::Quote,
+    render::{RenderContext, RenderTarget, render_blocks},
+};
+
+/// Matches the links to images on the site, e.g. `https://example.com/dynamic-data/images/m/<id>.webp`, or just the id.
+static IMAGE_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.*/dynamic-data/images/[sml]/)?(?<id>[0-9a-fA-F-]{36})(\.webp)?$").unwrap()
+});
+
+/// Escapes text so that it is shown as it is in Markdown.
+pub fn escape(text: &str) -> String {
+    escape_commonmark_inline(text)
+}
+
+/// The absolute address of an image on the site, so that it is shown also when the Markdown is opened elsewhere.
+pub fn image_url(id: &str) -> String {
+    escape_commonmark_link_destination(&format!(
+        "{}/dynamic-data/images/l/{}.webp",
+        env::var("SITE_URL").unwrap_or_default(),
+        id
+    ))
+}
+
+/// A part of a Markdown document that becomes one block.
+#[derive(Debug, PartialEq)]
+enum Part {
+    Heading {
+        heading: String,
+        level: HeadingLevel,
+    },
+    Quote {
+        quote: String,
+        citation: String,
+    },
+    /// An image that becomes an `Image` block if there is an image with the id, otherwise a paragraph with `source`.
+    Image {
+        id: Uuid,
+        caption: String,
+        source: String,
+    },
+    Paragraph(String),
+}
+
+/// Formats a node as Markdown.
+fn source(node: Node<'_>, options: &Options) -> String {
+    let mut source = String::new();
+    format_commonmark(node, options, &mut source).unwrap_or_default();
+    source.trim().to_string()
+}
+
+/// Gets the image of a paragraph that only holds an image.
+fn only_image<'a>(paragraph: Node<'a>) -> Option<Node<'a>> {
+    let mut children = paragraph.children().filter(|child| {
+        !matches!(
+            &child.data().value,
+            NodeValue::SoftBreak | NodeValue::LineBreak
+        ) && !matches!(&child.data().value, NodeValue::Text(text) if text.trim().is_empty())
+    });
+
+    match (children.next(), children.next()) {
+        (Some(image), None) if matches!(image.data().value, NodeValue::Image(_)) => Some(image),
+        _ => None,
+    }
+}
+
+/// Splits a Markdown document into the parts that become blocks.
+fn parse(markdown: &str) -> Vec<Part> {
+    let arena = Arena::new();
+    let options = Options::default();
+    let document = parse_document(&arena, markdown, &options);
+
+    document
+        .children()
+        .map(|node| match &node.data().value {
+            NodeValue::Heading(heading) => Part::Heading {
+                heading: node.collect_text().trim().to_string(),
+                level: HeadingLevel::from_number(heading.level),
+            },
+            NodeValue::BlockQuote => {
+                let mut paragraphs = node
+                    .children()
+                    .map(|child| child.collect_text().trim().to_string())
+                    .filter(|paragraph| !paragraph.is_empty())
+                    .collect::<Vec<String>>();
+                // A last paragraph like "— Someone" is the citation, as in `Quote::markdown`.
+                let citation = match paragraphs.last().and_then(|last| {
+                    last.strip_prefix('—')
+                        .or_else(|| last.strip_prefix('–'))
+                        .or_else(|| last.strip_prefix("--"))
+                }) {
+                    Some(citation) => {
+                        let citation = citation.trim().to_string();
+                        paragraphs.pop();
+                        citation
+                    }
+                    None => String::new(),
+                };
+
+                Part::Quote {
+                    quote: paragraphs.join("\n\n"),
+                    citation,
+                }
+            }
+            NodeValue::Paragraph => {
+                let image = only_image(node).and_then(|image| {
+                    let NodeValue::Image(link) = &image.data().value else {
+                        return None;
+                    };
+                    let id = IMAGE_URL_RE.captures(link.url.trim())?["id"].to_string();
+                    let caption = match link.title.trim() {
+                        "" => image.collect_text().trim().to_string(),
+                        title => title.to_string(),
+                    };
+                    Some((Uuid::from_str(&id).ok()?, caption))
+                });
+
+                match image {
+                    Some((id, caption)) => Part::Image {
+                        id,
+                        caption,
+                        source: source(node, &options),
+                    },
+                    None => Part::Paragraph(source(node, &options)),
+                }
+            }
+            _ => Part::Paragraph(source(node, &options)),
+        })
+        .filter(|part| !matches!(part, Part::Paragraph(source) if source.is_empty()))
+        .collect()
+}
+
+/// Turns a Markdown document into blocks: headings become `Heading`s, block quotes `Quote`s,
+/// images of the image gallery `Image`s, and everything else `Paragraph`s.
+pub async fn import(db: &DatabaseHandler, markdown: &str) -> Result<Vec<Block>, Error> {
+    let parts = parse(markdown);
+
+    let image_ids = parts
+        .iter()
+        .filter_map(|part| match part {
+            Part::Image { id, .. } => Some(*id),
+            _ => None,
+        })
+        .collect::<Vec<Uuid>>();
+    let existing_image_ids = ImageData::get_by_ids(db, &image_ids)
+        .await?
+        .into_iter()
+        .map(|image| image.id)
+        .collect::<Vec<Uuid>>();
+
+    Ok(parts
+        .into_iter()
+        .map(|part| match part {
+            Part::Heading { heading, level } => Block::new(Heading { heading, level }),
+            Part::Quote { quote, citation } => Block::new(Quote { quote, citation }),
+            Part::Image {
+                id,
+                caption,
+                source,
+            } => match existing_image_ids.contains(&id) {
+                true => Block::new(Image {
+                    id: id.to_string(),
+                    caption,
+                    image_data: None,
+                }),
+                false => Block::new(Paragraph { body_text: source }),
+            },
+            Part::Paragraph(body_text) => Block::new(Paragraph { body_text }),
+        })
+        .collect())
+}
+
+/// Turns a text or page into a Markdown document, with the title as the only first level heading.
+pub fn export(title: &str, lead_paragraph: Option<&str>, blocks: &[Block]) -> String {
+    let mut parts = vec![format!("# {}", escape(title))];
+    parts.extend(lead_paragraph.map(|lead_paragraph| format!("**{}**", escape(lead_paragraph))));
+    let body = render_blocks(&RenderContext::new(RenderTarget::Markdown), blocks);
+    parts.extend(Some(body).filter(|body| !body.is_empty()));
+
+    parts.join("\n\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that every kind of Markdown ends up in the right kind of block.
+    #[test]
+    fn test_parse() {
+        let parts = parse(
+            "# Rubrik\n\n### Underrubrik\n\nEtt *stycke*\nmed två rader.\n\n> Citat\n>\n> — Någon\n\n![Katt](https://example.com/dynamic-data/images/m/11111111-1111-1111-1111-111111111111.webp)\n\n![Extern](https://example.com/katt.png)\n\n- Ett\n- Två\n",
+        );
+
+        assert_eq!(
+            parts,
+            [
+                Part::Heading {
+                    heading: "Rubrik".to_string(),
+                    level: HeadingLevel::H2
+                },
+                Part::Heading {
+                    heading: "Underrubrik".to_string(),
+                    level: HeadingLevel::H3
+                },
+                Part::Paragraph("Ett *stycke*\nmed två rader.".to_string()),
+                Part::Quote {
+                    quote: "Citat".to_string(),
+                    citation: "Någon".to_string()
+                },
+                Part::Image {
+                    id: Uuid::from_str("11111111-1111-1111-1111-111111111111").unwrap(),
+                    caption: "Katt".to_string(),
+                    source: "![Katt](https://example.com/dynamic-data/images/m/11111111-1111-1111-1111-111111111111.webp)".to_string()
+                },
+                Part::Paragraph("![Extern](https://example.com/katt.png)".to_string()),
+                Part::Paragraph("- Ett\n- Två".to_string()),
+            ]
+        );
+    }
+}
@@ -1,144 +1,389 @@
-use std::str::FromStr;
+use std::{
+    any::Any,
+    fmt::{self, Debug},
+    sync::OnceLock,
+};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{DeserializeOwned, Error as _},
+    ser::{Error as _, SerializeMap},
+};
+use serde_json::Value;
 
-use comrak::{Options, markdown_to_html};
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use rocket::http::Status;
 
+use self::{
+    render::{RenderContext, RenderNeeds, RenderTarget},
+    validation::FieldErrors,
+};
 use crate::{
-    database::{DatabaseHandler, models::image::Image},
+    database::{DatabaseHandler, models::creator::Creator},
     error::Error,
 };
 
-/// Different colors that can be used for the text box.
-/// These reflect the colors in our graphical profile.
+pub mod audio;
+pub mod embed;
+pub mod footnotes;
+pub mod gallery;
+pub mod heading;
+pub mod html;
+pub mod image;
+pub mod markdown;
+pub mod paragraph;
+pub mod poll;
+pub mod quote;
+pub mod raw_html;
+pub mod render;
+pub mod snippet;
+pub mod table;
+pub mod table_of_contents;
+pub mod text_box;
+pub mod text_reference;
+pub mod validation;
+pub mod youtube;
+
+/// A type of block in the block editor, e.g. a paragraph or an image.
 ///
-/// The color names should be self explanatory.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub enum TextBoxColor {
-    Grey,
-    Blue,
-    Green,
-    Red,
-    Yellow,
-}
-
-impl TextBoxColor {
-    fn as_str(&self) -> &'static str {
-        match self {
-            TextBoxColor::Grey => "grey",
-            TextBoxColor::Blue => "blue",
-            TextBoxColor::Green => "green",
-            TextBoxColor::Red => "red",
-            TextBoxColor::Yellow => "yellow",
-        }
+/// Every block type lives in its own module and is made known to the rest of the site
+/// by registering it in the `BlockRegistry` when the server starts.
+/// A block is stored as its serialized fields plus a `type` field with `BlockSchema::type_name`,
+/// so renaming a field or the type breaks already stored texts, unless the old type is kept with
+/// `BlockRegistry::register_legacy`.
+#[rocket::async_trait]
+pub trait BlockKind: ErasedBlockKind + Debug + Send + Sync + 'static {
+    /// Describes the block type and how it is edited, see `BlockSchema`.
+    fn schema() -> BlockSchema
+    where
+        Self: Sized;
+
+    /// Adds what the block needs from the database to be rendered to `needs`, e.g. the ids of its images.
+    /// It is loaded for all blocks at once into the `RenderContext` given to `render`.
+    fn needs(&self, _needs: &mut RenderNeeds) {}
+
+    /// Renders the block as HTML for the `Site` or `Feed` target of `context`.
+    /// For feeds, it must not contain iframes, scripts or forms, and addresses on the site must come from `RenderContext::url`.
+    fn render(&self, context: &RenderContext) -> Result<String, Error>;
+
+    /// Checks that the block can be saved, and adds everything that is wrong to `errors`.
+    async fn validate(&self, _db: &DatabaseHandler, _errors: &mut FieldErrors) {}
+
+    /// Adds everything that does not stop the block from being saved, but that the creator
+    /// should know about, to `warnings`, e.g. that a referenced text is not published.
+    async fn warn(&self, _db: &DatabaseHandler, _warnings: &mut FieldErrors) {}
+
+    /// The text of the block without any markup, for the `PlainText` target, e.g. for search.
+    fn plain_text(&self) -> String;
+
+    /// The block as Markdown for the `Markdown` target, e.g. when exporting a text; by default its plain text.
+    fn markdown(&self) -> String {
+        markdown::escape(&self.plain_text())
     }
+
+    /// Whether only publishers may add or change blocks of this type.
+    fn is_publisher_only(&self) -> bool {
+        false
+    }
+
+    /// Cleans up the block before it is stored, e.g. removes disallowed HTML.
+    fn sanitize(&mut self) {}
 }
 
-/// These are all the different types of blocks supported by the block editor.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(tag = "type")]
-pub enum Block {
-    /// A paragraph of text, it is stored as markdown, so formatting is possible.
-    Paragraph { body_text: String },
-    /// An image with a caption.
-    Image {
-        id: String,
-        caption: String,
-        image_data: Option<Image>,
-    },
-    /// A quote with a citation.
-    Quote { quote: String, citation: String },
-    /// Heading is a simple H2.
-    Heading { heading: String },
-    /// Raw html blocks. This should preferably be used as little as possible...
-    RawHtml { html: String },
-    /// Embeds a Youtube video.
-    YouTube {
-        video_link: String,
-        caption: Option<String>,
-    },
-    /// A text box; a paragraph with a background color plate.
-    TextBox {
-        text: String,
-        color: Option<TextBoxColor>,
-    },
+/// The parts of a `BlockKind` that are the same for all block types.
+/// This is implemented automatically for every `BlockKind` that is `Clone` and `Serialize`.
+pub trait ErasedBlockKind {
+    /// The `type` of the block, see `BlockSchema::type_name`.
+    fn type_name(&self) -> &'static str;
+    /// The fields of the block, without the `type`.
+    fn to_json(&self) -> Result<Value, serde_json::Error>;
+    fn clone_kind(&self) -> Box<dyn BlockKind>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: BlockKind + Clone + Serialize> ErasedBlockKind for T {
+    fn type_name(&self) -> &'static str {
+        T::schema().type_name
+    }
+
+    fn to_json(&self) -> Result<Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+
+    fn clone_kind(&self) -> Box<dyn BlockKind> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
+/// Describes a block type for the block editor.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockSchema {
+    /// The `type` of the block in the stored JSON, e.g. `Paragraph`.
+    pub type_name: &'static str,
+    /// The name of the block type shown to creators.
+    pub label: &'static str,
+    pub fields: Vec<BlockField>,
+}
+
+/// A field of a block that can be edited in the block editor.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockField {
+    /// The name of the field in the stored JSON.
+    pub name: &'static str,
+    /// Shown above the input; `None` for blocks that only have one, obvious, field.
+    pub label: Option<&'static str>,
+    pub placeholder: &'static str,
+    pub input: FieldInput,
+}
+
+/// How a `BlockField` is edited.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum FieldInput {
+    /// A single line of text.
+    Text,
+    /// Several lines of text.
+    TextArea,
+    /// One of a fixed set of options.
+    Select { options: Vec<SelectOption> },
+    /// An ordered list of images from the image gallery, each with a caption.
+    ImageList,
+    /// A date and time, as `YYYY-MM-DDTHH:MM` in local time.
+    DateTime,
+    /// Not shown to the creator, e.g. an id that is set when the block is saved.
+    Hidden,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectOption {
+    pub value: &'static str,
+    pub label: &'static str,
+}
+
+/// A block in the block editor, of any of the types in the `BlockRegistry`.
+pub struct Block(Box<dyn BlockKind>);
+
 impl Block {
-    pub async fn render(&self, db: &DatabaseHandler) -> Result<String, Error> {
-        match self {
-            Block::Heading { heading } => Ok(format!("<h2>{}</h2>", heading)),
-            Block::Paragraph { body_text } => Ok(markdown_to_html(body_text, &Options::default())),
-            Block::Quote { quote, citation } => Ok(format!(
-                r#"<blockquote cite="{}">{}</blockquote>"#,
-                citation, quote
-            )),
-            Block::Image {
-                id,
-                caption,
-                image_data: _,
-            } => {
-                let image_id = Uuid::from_str(id)?;
-                let image_data = Image::get_by_id(db, image_id).await?;
-
-                Ok(format!(
-                    r#"<img src="/dynamic-data/images/m/{}.webp" alt="{}" /><p class="caption">{} <span>Foto: {}.</span></p>"#,
-                    image_data.id,
-                    image_data.description.unwrap_or_default(),
-                    caption,
-                    image_data.author,
-                ))
-            }
-            Block::RawHtml { html } => Ok(html.to_string()),
-            Block::YouTube {
-                video_link,
-                caption,
-            } => {
-                let video_id_re = Regex::new(
-                    r"(https://)?(youtu\.be|youtube\.com|www\.youtube\.com)/(watch\?v=|shorts|live)?\/?",
-                )?;
-                let video_id = video_id_re.replace(video_link, "");
-                Ok(format!(
-                    r#"<iframe class="youtube-video" src="https://www.youtube.com/embed/{}" title="YouTube video player" frameborder="0" allowfullscreen></iframe><p class="caption">{}</p>"#,
-                    video_id,
-                    caption.as_ref().unwrap_or(&"".to_string())
-                ))
+    pub fn new(kind: impl BlockKind) -> Self {
+        Self(Box::new(kind))
+    }
+
+    /// The `type` of the block, see `BlockSchema::type_name`.
+    pub fn type_name(&self) -> &'static str {
+        self.0.type_name()
+    }
+
+    /// Gets the block as a `T`, if it is of that type.
+    pub fn downcast_ref<T: BlockKind>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref::<T>()
+    }
+
+    pub fn needs(&self, needs: &mut RenderNeeds) {
+        self.0.needs(needs)
+    }
+
+    /// Renders the block as the target of `context`, see `render::render_body` for rendering whole texts.
+    pub fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        match context.target() {
+            RenderTarget::Site | RenderTarget::Feed => self.0.render(context),
+            RenderTarget::PlainText => Ok(self.0.plain_text()),
+            RenderTarget::Markdown => Ok(self.0.markdown()),
+        }
+    }
+
+    /// Sanitizes `blocks` before they are stored.
+    /// Blocks that only publishers may add or change must be unchanged from `previous` if `executor` is not a publisher.
+    pub fn prepare_for_saving(
+        executor: &Creator,
+        mut blocks: Vec<Self>,
+        previous: &[Self],
+    ) -> Result<Vec<Self>, Error> {
+        for block in blocks.iter_mut() {
+            block.0.sanitize();
+
+            if block.0.is_publisher_only() && !executor.is_publisher() && !previous.contains(block)
+            {
+                return Err(Error::create(
+                    &format!("{}:{}", file!(), line!()),
+                    &format!(
+                        "Must be publisher to add or change blocks of type `{}`!",
+                        block.type_name()
+                    ),
+                    Status::Unauthorized,
+                ));
             }
-            Block::TextBox { text, color } => Ok(format!(
-                "<div class=\"textbox {}\">{}</div>",
-                color.as_ref().map(|color| color.as_str()).unwrap_or(""),
-                markdown_to_html(text, &Options::default())
-            )),
         }
+
+        Ok(blocks)
     }
 }
 
-// FIXME: This should be a trait, and all the different types should implement it.
-// It will probably take some time to change it, but it's worth it to have a more modular way of adding modules.
-// It would also (maybe?) make it possible to add in external modules for the block editor.
+impl Clone for Block {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_kind())
+    }
+}
+
+impl Debug for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for Block {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_name() == other.type_name()
+            && matches!((self.0.to_json(), other.0.to_json()), (Ok(a), Ok(b)) if a == b)
+    }
+}
+
+impl Serialize for Block {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = match self.0.to_json().map_err(S::Error::custom)? {
+            Value::Object(fields) => fields,
+            _ => return Err(S::Error::custom("A block must serialize to an object!")),
+        };
+
+        let mut map = serializer.serialize_map(Some(fields.len() + 1))?;
+        map.serialize_entry("type", self.type_name())?;
+        for (name, value) in fields {
+            map.serialize_entry(&name, &value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Block {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let type_name = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("type"))?;
+
+        let kind = registry()
+            .get(type_name)
+            .ok_or_else(|| D::Error::custom(format!("unknown block type `{}`", type_name)))?;
+
+        (kind.deserialize)(value.clone()).map_err(D::Error::custom)
+    }
+}
+
+/// A block type in the `BlockRegistry`.
+struct RegisteredKind {
+    type_name: &'static str,
+    /// `None` for block types that can be loaded but not edited, see `BlockRegistry::register_legacy`.
+    schema: Option<BlockSchema>,
+    deserialize: fn(Value) -> Result<Block, serde_json::Error>,
+}
+
+/// All the block types that can be stored and edited, in the order they are offered in the editor.
+#[derive(Default)]
+pub struct BlockRegistry {
+    kinds: Vec<RegisteredKind>,
+}
+
+impl BlockRegistry {
+    /// A registry with all the block types that come with the site.
+    pub fn with_builtin_kinds() -> Self {
+        let mut registry = Self::default();
+        registry
+            .register::<paragraph::Paragraph>()
+            .register::<image::Image>()
+            .register::<quote::Quote>()
+            .register::<heading::Heading>()
+            .register::<raw_html::RawHtml>()
+            .register::<embed::Embed>()
+            .register::<text_box::TextBox>()
+            .register::<table::Table>()
+            .register::<text_reference::TextReference>()
+            .register::<poll::Poll>()
+            .register::<gallery::Gallery>()
+            .register::<audio::Audio>()
+            .register::<snippet::Snippet>()
+            .register_legacy::<youtube::YouTube>("YouTube");
+        registry
+    }
+
+    /// Adds a block type, replacing any earlier block type with the same `type`.
+    pub fn register<T: BlockKind + DeserializeOwned>(&mut self) -> &mut Self {
+        let schema = T::schema();
+        self.kinds.retain(|kind| kind.type_name != schema.type_name);
+        self.kinds.push(RegisteredKind {
+            type_name: schema.type_name,
+            schema: Some(schema),
+            deserialize: |value| serde_json::from_value::<T>(value).map(Block::new),
+        });
+        self
+    }
+
+    /// Adds a block type that has been replaced by another, so that texts stored with it can still be loaded.
+    /// Blocks of the type are turned into the new type when they are loaded, and are saved as it.
+    pub fn register_legacy<T: DeserializeOwned + Into<Block>>(
+        &mut self,
+        type_name: &'static str,
+    ) -> &mut Self {
+        self.kinds.retain(|kind| kind.type_name != type_name);
+        self.kinds.push(RegisteredKind {
+            type_name,
+            schema: None,
+            deserialize: |value| serde_json::from_value::<T>(value).map(Into::into),
+        });
+        self
+    }
+
+    fn get(&self, type_name: &str) -> Option<&RegisteredKind> {
+        self.kinds.iter().find(|kind| kind.type_name == type_name)
+    }
+
+    /// The schemas of all block types that can be edited, for the block editor.
+    pub fn schemas(&self) -> Vec<BlockSchema> {
+        self.kinds
+            .iter()
+            .filter_map(|kind| kind.schema.clone())
+            .collect()
+    }
+}
+
+static REGISTRY: OnceLock<BlockRegistry> = OnceLock::new();
+
+/// Makes `registry` the registry used everywhere blocks are (de)serialized.
+/// This must be done when the server starts, before any block is loaded.
+pub fn install_registry(registry: BlockRegistry) {
+    if REGISTRY.set(registry).is_err() {
+        panic!("The block registry has already been installed or used!");
+    }
+}
+
+/// Gets the installed `BlockRegistry`, or the built-in block types if none has been installed (e.g. in tests).
+pub fn registry() -> &'static BlockRegistry {
+    REGISTRY.get_or_init(BlockRegistry::with_builtin_kinds)
+}
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use super::{image::Image, paragraph::Paragraph, quote::Quote, *};
 
-    /// Tests that the syntax of the `Block` enum and its variants is
-    /// correct and can be serialized and deserialized correctly.
+    /// Tests that blocks are serialized with their fields and a `type` tag,
+    /// and that the `BlockRegistry` deserializes them back into the same block types.
     #[test]
     fn test_syntax() {
         let article = [
-            Block::Paragraph {
+            Block::new(Paragraph {
                 body_text: "Hello, world!".to_string(),
-            },
-            Block::Image {
+            }),
+            Block::new(Image {
                 id: "1".to_string(),
                 caption: "Hello, world!".to_string(),
                 image_data: None,
-            },
-            Block::Quote {
+            }),
+            Block::new(Quote {
                 quote: "Hello, world!".to_string(),
                 citation: "Hello, world!".to_string(),
-            },
+            }),
         ];
 
         let article_json = serde_json::to_string(&article).unwrap();
@@ -153,4 +398,25 @@ mod tests {
         assert_eq!(article_json, expected_article_json);
         assert_eq!(article_parsed_string, expected_article_parsed);
     }
+
+    /// Tests that unknown block types are rejected instead of silently dropped.
+    #[test]
+    fn test_unknown_type() {
+        assert!(serde_json::from_str::<Block>(r#"{"type":"Nonexistent"}"#).is_err());
+        assert!(serde_json::from_str::<Block>(r#"{"body_text":"Hello, world!"}"#).is_err());
+    }
+
+    /// Tests that blocks of replaced types are loaded as the type that replaced them.
+    #[test]
+    fn test_legacy_type() {
+        let block: Block = serde_json::from_str(
+            r#"{"type":"YouTube","video_link":"https://youtu.be/dQw4w9WgXcQ","caption":null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&block).unwrap(),
+            r#"{"type":"Embed","link":"https://youtu.be/dQw4w9WgXcQ","caption":null}"#
+        );
+    }
 }
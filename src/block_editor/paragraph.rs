@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, defaults::BLOCK_LONG_FIELD_MAX_CHARS, error::Error};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, footnotes, render::RenderContext,
+    validation::FieldErrors,
+};
+
+/// A paragraph of text, it is stored as markdown, so formatting is possible.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Paragraph {
+    pub body_text: String,
+}
+
+#[rocket::async_trait]
+impl BlockKind for Paragraph {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Paragraph",
+            label: "Brödtext",
+            fields: vec![BlockField {
+                name: "body_text",
+                label: None,
+                placeholder: "Skriv brödtext här... Källhänvisningar skrivs som ^[Källan].",
+                input: FieldInput::TextArea,
+            }],
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        Ok(footnotes::render_markdown(&self.body_text, context))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors.required("body_text", &self.body_text).max_length(
+            "body_text",
+            &self.body_text,
+            BLOCK_LONG_FIELD_MAX_CHARS,
+        );
+        footnotes::validate("body_text", &self.body_text, errors);
+    }
+
+    fn plain_text(&self) -> String {
+        footnotes::plain_text(&self.body_text)
+    }
+
+    fn markdown(&self) -> String {
+        self.body_text.trim().to_string()
+    }
+}
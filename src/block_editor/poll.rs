@@ -0,0 +1,247 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    database::{DatabaseHandler, models::poll::PollVote},
+    defaults::{BLOCK_SHORT_FIELD_MAX_CHARS, POLL_MAX_OPTIONS},
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, html, markdown,
+    render::{RenderContext, RenderNeeds, RenderTarget},
+    validation::FieldErrors,
+};
+
+/// The format of `Poll::closes_at`, as given by `<input type="datetime-local">`.
+const CLOSES_AT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// A question that readers can vote on. The votes are stored as `PollVote`s.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Poll {
+    /// Identifies the poll among the `PollVote`s; set when the poll is first saved, see `Poll::sanitize`.
+    #[serde(default)]
+    pub id: String,
+    pub question: String,
+    /// One option per line. The votes are stored per option index, so the order must not change once readers have voted.
+    pub options: String,
+    /// When the poll stops taking votes, in local time, or empty if it never closes.
+    #[serde(default)]
+    pub closes_at: String,
+}
+
+impl Poll {
+    pub fn uuid(&self) -> Option<Uuid> {
+        Uuid::from_str(&self.id).ok()
+    }
+
+    /// The options that readers can choose between, in order.
+    pub fn option_list(&self) -> Vec<&str> {
+        self.options
+            .lines()
+            .map(str::trim)
+            .filter(|option| !option.is_empty())
+            .collect()
+    }
+
+    /// When the poll closes, or `None` if it never closes or `closes_at` is not a valid time.
+    pub fn closing_time(&self) -> Option<DateTime<Local>> {
+        NaiveDateTime::parse_from_str(self.closes_at.trim(), CLOSES_AT_FORMAT)
+            .ok()
+            .and_then(|closes_at| closes_at.and_local_timezone(Local).earliest())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self.closing_time(), Some(closes_at) if closes_at <= Local::now())
+    }
+
+    /// Renders the number of votes for every option; shared with `static/scripts/poll.js`, which renders the same markup.
+    pub fn render_results(&self, votes: &[i64]) -> String {
+        let total = votes.iter().sum::<i64>();
+
+        format!(
+            r#"<ol class="poll-results">{}</ol><p class="poll-total">{} röster totalt.</p>"#,
+            self.option_list()
+                .iter()
+                .zip(votes)
+                .map(|(option, &votes)| {
+                    let percent = if total > 0 { votes * 100 / total } else { 0 };
+                    format!(
+                        r#"<li><span class="option">{}</span> <span class="share">{} % ({} röster)</span><progress max="100" value="{}"></progress></li>"#,
+                        html::escape(option),
+                        percent,
+                        votes,
+                        percent,
+                    )
+                })
+                .collect::<String>(),
+            total
+        )
+    }
+}
+
+#[rocket::async_trait]
+impl BlockKind for Poll {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Poll",
+            label: "Omröstning",
+            fields: vec![
+                BlockField {
+                    name: "id",
+                    label: None,
+                    placeholder: "",
+                    input: FieldInput::Hidden,
+                },
+                BlockField {
+                    name: "question",
+                    label: Some("Fråga:"),
+                    placeholder: "Vad tycker du om...?",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "options",
+                    label: Some("Alternativ (ett per rad):"),
+                    placeholder: "Ja\nNej\nVet inte",
+                    input: FieldInput::TextArea,
+                },
+                BlockField {
+                    name: "closes_at",
+                    label: Some("Stänger (lämna tomt för att aldrig stänga):"),
+                    placeholder: "",
+                    input: FieldInput::DateTime,
+                },
+            ],
+        }
+    }
+
+    fn needs(&self, needs: &mut RenderNeeds) {
+        // Only closed polls show their results to everyone
+        if let Some(id) = self.uuid().filter(|_| self.is_closed()) {
+            needs.poll_votes(id);
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        let Some(id) = self.uuid() else {
+            return Ok(String::new());
+        };
+
+        // Readers who have voted are shown the results by `static/scripts/poll.js` instead of the form.
+        let body = if self.is_closed() {
+            format!(
+                r#"<p class="question">{}</p><p class="poll-closed">Omröstningen är stängd.</p>{}"#,
+                html::escape(self.question.trim()),
+                self.render_results(&context.poll_votes(id, self.option_list().len()))
+            )
+        } else if context.target() == RenderTarget::Feed {
+            // Feed readers do not submit forms, so readers are shown the options and asked to vote on the site.
+            format!(
+                r#"<p class="question">{}</p><ul>{}</ul><p>Rösta på webbplatsen.</p>"#,
+                html::escape(self.question.trim()),
+                self.option_list()
+                    .iter()
+                    .map(|option| format!("<li>{}</li>", html::escape(option)))
+                    .collect::<String>(),
+            )
+        } else {
+            format!(
+                r#"<form action="/api/poll/{}/vote" method="post"><fieldset><legend>{}</legend>{}</fieldset><button type="submit" class="btn">Rösta</button>{}</form>"#,
+                id,
+                html::escape(self.question.trim()),
+                self.option_list()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, option)| format!(
+                        r#"<label><input type="radio" name="option" value="{}" required> {}</label>"#,
+                        index,
+                        html::escape(option)
+                    ))
+                    .collect::<String>(),
+                self.closing_time()
+                    .map(|closes_at| format!(
+                        r#"<p class="poll-closes">Omröstningen stänger {}.</p>"#,
+                        closes_at.format("%Y-%m-%d %H:%M")
+                    ))
+                    .unwrap_or_default(),
+            )
+        };
+
+        Ok(format!(
+            r#"<section class="poll" id="poll-{id}" data-poll-id="{id}" aria-label="Omröstning">{}</section>"#,
+            body,
+        ))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors.required("question", &self.question).max_length(
+            "question",
+            &self.question,
+            BLOCK_SHORT_FIELD_MAX_CHARS,
+        );
+
+        let options = self.option_list();
+        if options.len() < 2 {
+            errors.add("options", "Omröstningen måste ha minst två alternativ.");
+        }
+        if options.len() > POLL_MAX_OPTIONS {
+            errors.add(
+                "options",
+                &format!("Omröstningen får ha högst {} alternativ.", POLL_MAX_OPTIONS),
+            );
+        }
+        for option in options {
+            errors.max_length("options", option, BLOCK_SHORT_FIELD_MAX_CHARS);
+        }
+
+        if !self.closes_at.trim().is_empty() && self.closing_time().is_none() {
+            errors.add("closes_at", "Tiden är ogiltig.");
+        }
+    }
+
+    async fn warn(&self, db: &DatabaseHandler, warnings: &mut FieldErrors) {
+        let Some(id) = self.uuid() else {
+            return;
+        };
+
+        let votes = PollVote::count(db, id, self.option_list().len())
+            .await
+            .map(|votes| votes.iter().sum::<i64>())
+            .unwrap_or_default();
+        if votes > 0 {
+            warnings.add(
+                "options",
+                &format!(
+                    "{} läsare har redan röstat. Rösterna sparas per alternativ, så ändra inte ordningen på alternativen.",
+                    votes
+                ),
+            );
+        }
+    }
+
+    fn plain_text(&self) -> String {
+        format!("{}\n{}", self.question, self.option_list().join("\n"))
+    }
+
+    fn markdown(&self) -> String {
+        format!(
+            "**{}**\n\n{}",
+            markdown::escape(self.question.trim()),
+            self.option_list()
+                .iter()
+                .map(|option| format!("- {}", markdown::escape(option)))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+
+    /// Gives new polls an id, so that their votes can be stored.
+    fn sanitize(&mut self) {
+        if self.uuid().is_none() {
+            self.id = Uuid::new_v4().to_string();
+        }
+    }
+}
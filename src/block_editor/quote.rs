@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::DatabaseHandler,
+    defaults::{BLOCK_LONG_FIELD_MAX_CHARS, BLOCK_SHORT_FIELD_MAX_CHARS},
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, html, markdown, render::RenderContext,
+    validation::FieldErrors,
+};
+
+/// A quote with a citation.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Quote {
+    pub quote: String,
+    pub citation: String,
+}
+
+#[rocket::async_trait]
+impl BlockKind for Quote {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Quote",
+            label: "Citat",
+            fields: vec![
+                BlockField {
+                    name: "quote",
+                    label: Some("Citat:"),
+                    placeholder: "Skriv citat här...",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "citation",
+                    label: Some("Referens:"),
+                    placeholder: "Skriv vem som sa/skrev det här...",
+                    input: FieldInput::Text,
+                },
+            ],
+        }
+    }
+
+    fn render(&self, _context: &RenderContext) -> Result<String, Error> {
+        Ok(format!(
+            r#"<blockquote cite="{}">{}</blockquote>"#,
+            html::escape(&self.citation),
+            html::escape(&self.quote)
+        ))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors
+            .required("quote", &self.quote)
+            .max_length("quote", &self.quote, BLOCK_LONG_FIELD_MAX_CHARS)
+            .max_length("citation", &self.citation, BLOCK_SHORT_FIELD_MAX_CHARS);
+    }
+
+    fn plain_text(&self) -> String {
+        format!("{} – {}", self.quote, self.citation)
+    }
+
+    /// A block quote with the citation last, e.g. `> — Someone`.
+    fn markdown(&self) -> String {
+        let mut lines = self
+            .quote
+            .trim()
+            .lines()
+            .map(|line| {
+                format!("> {}", markdown::escape(line))
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<String>>();
+        if !self.citation.trim().is_empty() {
+            lines.push(">".to_string());
+            lines.push(format!("> — {}", markdown::escape(self.citation.trim())));
+        }
+
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, defaults::BLOCK_LONG_FIELD_MAX_CHARS, error::Error};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, html,
+    render::{RenderContext, RenderTarget},
+    validation::FieldErrors,
+};
+
+/// Raw html blocks. This should preferably be used as little as possible...
+///
+/// Only publishers may add or change them, and they are stored sanitized against the
+/// allowlist in `html::sanitize_raw_html`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RawHtml {
+    pub html: String,
+}
+
+#[rocket::async_trait]
+impl BlockKind for RawHtml {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "RawHtml",
+            label: "HTML",
+            fields: vec![BlockField {
+                name: "html",
+                label: None,
+                placeholder: "Bädda in din HTML här.",
+                input: FieldInput::TextArea,
+            }],
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        // Stored blocks are already sanitized, but older ones may not be.
+        let html = html::sanitize_raw_html(&self.html);
+        Ok(match context.target() {
+            // `RAW_HTML_ALLOWED_TAGS` may be set to include iframes, which feed readers do not show.
+            RenderTarget::Feed => html::sanitize(&html),
+            _ => html,
+        })
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors.required("html", &self.html).max_length(
+            "html",
+            &self.html,
+            BLOCK_LONG_FIELD_MAX_CHARS,
+        );
+    }
+
+    fn plain_text(&self) -> String {
+        html::plain_text(&self.html)
+    }
+
+    /// Markdown may contain HTML, so the HTML is kept as it is.
+    fn markdown(&self) -> String {
+        self.html.trim().to_string()
+    }
+
+    fn is_publisher_only(&self) -> bool {
+        true
+    }
+
+    fn sanitize(&mut self) {
+        self.html = html::sanitize_raw_html(&self.html);
+    }
+}
@@ -0,0 +1,311 @@
+//! Renders blocks with everything they need from the database loaded up front, so that rendering a text,
+//! or a whole feed of texts, takes the same few queries however many blocks there are.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    env,
+};
+
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        DatabaseHandler,
+        models::{
+            article::Text, audio::Audio, image::Image as ImageData, poll::PollVote,
+            snippet::Snippet,
+        },
+    },
+    error::Error,
+};
+
+use super::{Block, footnotes, table_of_contents::Anchors};
+
+/// What blocks are rendered as, see `Block::render`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum RenderTarget {
+    /// HTML for the site.
+    #[default]
+    Site,
+    /// HTML for feed readers, e.g. the Atom feed: without iframes, scripts or forms, and with absolute addresses.
+    Feed,
+    /// The text without any markup, e.g. for search, see `BlockKind::plain_text`.
+    PlainText,
+    /// Markdown, e.g. when exporting a text, see `BlockKind::markdown`.
+    Markdown,
+}
+
+impl RenderTarget {
+    /// Whether blocks are rendered as HTML, and so need what `BlockKind::needs` asks for.
+    pub fn is_html(self) -> bool {
+        matches!(self, Self::Site | Self::Feed)
+    }
+}
+
+/// What a set of blocks needs from the database to be rendered, see `BlockKind::needs`.
+#[derive(Debug, Default)]
+pub struct RenderNeeds {
+    image_ids: HashSet<Uuid>,
+    text_ids: HashSet<i32>,
+    poll_ids: HashSet<Uuid>,
+    audio_ids: HashSet<Uuid>,
+    snippet_ids: HashSet<Uuid>,
+}
+
+impl RenderNeeds {
+    pub fn image(&mut self, id: Uuid) -> &mut Self {
+        self.image_ids.insert(id);
+        self
+    }
+
+    /// A text that is shown if it is published.
+    pub fn text(&mut self, id: i32) -> &mut Self {
+        self.text_ids.insert(id);
+        self
+    }
+
+    /// The votes of a poll.
+    pub fn poll_votes(&mut self, id: Uuid) -> &mut Self {
+        self.poll_ids.insert(id);
+        self
+    }
+
+    pub fn audio(&mut self, id: Uuid) -> &mut Self {
+        self.audio_ids.insert(id);
+        self
+    }
+
+    /// A snippet, along with everything its blocks need.
+    pub fn snippet(&mut self, id: Uuid) -> &mut Self {
+        self.snippet_ids.insert(id);
+        self
+    }
+}
+
+/// Everything that blocks need from the database to be rendered, loaded with one query per kind of data.
+#[derive(Debug, Default)]
+pub struct RenderContext {
+    target: RenderTarget,
+    images: HashMap<Uuid, ImageData>,
+    published_texts: HashMap<i32, Text>,
+    poll_votes: HashMap<Uuid, Vec<i64>>,
+    audio: HashMap<Uuid, Audio>,
+    snippets: HashMap<Uuid, Snippet>,
+    /// Whether the blocks of a snippet are being rendered, see `render_snippet`.
+    in_snippet: Cell<bool>,
+    /// The sources of the footnotes in the body being rendered, in the order they are numbered.
+    footnotes: RefCell<Vec<String>>,
+    /// The anchors of the headings in the body being rendered.
+    anchors: RefCell<Anchors>,
+}
+
+impl RenderContext {
+    /// A context without anything from the database, enough for the `PlainText` and `Markdown` targets.
+    pub fn new(target: RenderTarget) -> Self {
+        Self {
+            target,
+            ..Default::default()
+        }
+    }
+
+    /// Loads everything that `blocks` need to be rendered as `target`, see `BlockKind::needs`.
+    pub async fn load<'a>(
+        db: &DatabaseHandler,
+        blocks: impl IntoIterator<Item = &'a Block>,
+        target: RenderTarget,
+    ) -> Result<Self, Error> {
+        let mut context = Self::new(target);
+        if !target.is_html() {
+            return Ok(context);
+        }
+
+        let mut needs = RenderNeeds::default();
+        for block in blocks {
+            block.needs(&mut needs);
+        }
+
+        // Snippets are loaded first, as their blocks need things of their own.
+        if !needs.snippet_ids.is_empty() {
+            let ids = needs.snippet_ids.drain().collect::<Vec<Uuid>>();
+            context.snippets = Snippet::get_by_ids(db, &ids)
+                .await?
+                .into_iter()
+                .map(|snippet| (snippet.id, snippet))
+                .collect();
+            for block in context
+                .snippets
+                .values()
+                .flat_map(|snippet| snippet.text_body.iter())
+            {
+                block.needs(&mut needs);
+            }
+        }
+
+        if !needs.image_ids.is_empty() {
+            let ids = needs.image_ids.into_iter().collect::<Vec<Uuid>>();
+            context.images = ImageData::get_by_ids(db, &ids)
+                .await?
+                .into_iter()
+                .map(|image| (image.id, image))
+                .collect();
+        }
+        if !needs.text_ids.is_empty() {
+            let ids = needs.text_ids.into_iter().collect::<Vec<i32>>();
+            context.published_texts = Text::get_by_ids(db, &ids, true)
+                .await?
+                .into_iter()
+                .map(|text| (text.id, text))
+                .collect();
+        }
+        if !needs.poll_ids.is_empty() {
+            let ids = needs.poll_ids.into_iter().collect::<Vec<Uuid>>();
+            context.poll_votes = PollVote::count_by_poll_ids(db, &ids).await?;
+        }
+        if !needs.audio_ids.is_empty() {
+            let ids = needs.audio_ids.into_iter().collect::<Vec<Uuid>>();
+            context.audio = Audio::get_by_ids(db, &ids)
+                .await?
+                .into_iter()
+                .map(|audio| (audio.id, audio))
+                .collect();
+        }
+
+        Ok(context)
+    }
+
+    /// Gets an image, unless it does not exist or is in the trash.
+    pub fn image(&self, id: Uuid) -> Option<&ImageData> {
+        self.images.get(&id)
+    }
+
+    pub fn target(&self) -> RenderTarget {
+        self.target
+    }
+
+    /// The address of `path` on the site, e.g. `/t/1/katter`, which is made absolute for the `Feed` target.
+    pub fn url(&self, path: &str) -> String {
+        match self.target {
+            RenderTarget::Feed => format!("{}{}", env::var("SITE_URL").unwrap_or_default(), path),
+            _ => path.to_string(),
+        }
+    }
+
+    /// Gets an audio file, unless it does not exist or is in the trash.
+    pub fn audio(&self, id: Uuid) -> Option<&Audio> {
+        self.audio.get(&id)
+    }
+
+    /// Gets a snippet, unless it does not exist.
+    pub fn snippet(&self, id: Uuid) -> Option<&Snippet> {
+        self.snippets.get(&id)
+    }
+
+    /// Renders the blocks of a snippet as part of the body being rendered.
+    /// Snippets within snippets render nothing, and the headings of a snippet get no anchors,
+    /// as they are not part of the table of contents of the body.
+    pub fn render_snippet(&self, blocks: &[Block]) -> String {
+        if self.in_snippet.replace(true) {
+            return String::new();
+        }
+        let rendered = blocks
+            .iter()
+            .map(|block| block.render(self).unwrap_or("INVALID BLOCK!".to_string()))
+            .collect::<String>();
+        self.in_snippet.set(false);
+        rendered
+    }
+
+    /// Gets a text, unless it does not exist or is not published.
+    pub fn published_text(&self, id: i32) -> Option<&Text> {
+        self.published_texts.get(&id)
+    }
+
+    /// Gets the number of votes for each of the first `options` options of a poll, as `PollVote::count`.
+    pub fn poll_votes(&self, id: Uuid, options: usize) -> Vec<i64> {
+        let mut votes = self.poll_votes.get(&id).cloned().unwrap_or_default();
+        votes.resize(options, 0);
+        votes
+    }
+
+    /// Numbers a footnote in the body being rendered, see `footnotes`; the same source always gets the same number.
+    /// Returns the number and whether it is the first footnote with it.
+    pub fn footnote(&self, source: &str) -> (usize, bool) {
+        let mut footnotes = self.footnotes.borrow_mut();
+        match footnotes.iter().position(|footnote| footnote == source) {
+            Some(index) => (index + 1, false),
+            None => {
+                footnotes.push(source.to_string());
+                (footnotes.len(), true)
+            }
+        }
+    }
+
+    /// Gives a heading in the body being rendered its anchor, the same as in `table_of_contents::table_of_contents`.
+    /// Returns `None` for headings in snippets, see `render_snippet`.
+    pub fn anchor(&self, heading: &str) -> Option<String> {
+        if self.in_snippet.get() {
+            return None;
+        }
+        Some(self.anchors.borrow_mut().anchor(heading))
+    }
+
+    /// Ends the body that has been rendered, so that the next body starts without footnotes or anchors.
+    /// Returns the sources of the footnotes in it.
+    pub fn end_body(&self) -> Vec<String> {
+        self.anchors.take();
+        self.footnotes.take()
+    }
+}
+
+/// Renders the blocks of one text or page with `context`, which must have been loaded for them.
+/// HTML ends with the sources if there are footnotes, and blocks that cannot be rendered are replaced with a notice.
+pub fn render_blocks(context: &RenderContext, blocks: &[Block]) -> String {
+    match context.target() {
+        RenderTarget::Site | RenderTarget::Feed => {
+            let rendered_blocks = blocks
+                .iter()
+                .map(|block| {
+                    block
+                        .render(context)
+                        .unwrap_or("INVALID BLOCK!".to_string())
+                })
+                .collect::<String>();
+            rendered_blocks + &footnotes::render_sources(&context.end_body())
+        }
+        RenderTarget::PlainText | RenderTarget::Markdown => blocks
+            .iter()
+            .filter_map(|block| block.render(context).ok())
+            .filter(|rendered| !rendered.trim().is_empty())
+            .collect::<Vec<String>>()
+            .join(match context.target() {
+                RenderTarget::PlainText => "\n",
+                _ => "\n\n",
+            }),
+    }
+}
+
+/// Renders the blocks of several texts or pages as `target`, e.g. for a feed, loading what they need in one go.
+/// Returns the rendered body of each, see `render_blocks`.
+pub async fn render_bodies(
+    db: &DatabaseHandler,
+    bodies: &[&[Block]],
+    target: RenderTarget,
+) -> Result<Vec<String>, Error> {
+    let context =
+        RenderContext::load(db, bodies.iter().flat_map(|blocks| blocks.iter()), target).await?;
+
+    Ok(bodies
+        .iter()
+        .map(|blocks| render_blocks(&context, blocks))
+        .collect())
+}
+
+/// Renders the blocks of one text or page as `target`, see `render_bodies`.
+pub async fn render_body(
+    db: &DatabaseHandler,
+    blocks: &[Block],
+    target: RenderTarget,
+) -> Result<String, Error> {
+    Ok(render_bodies(db, &[blocks], target).await?.concat())
+}
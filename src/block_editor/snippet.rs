@@ -0,0 +1,86 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    database::{DatabaseHandler, models::snippet::Snippet as SnippetData},
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput,
+    render::{RenderContext, RenderNeeds},
+    validation::FieldErrors,
+};
+
+/// Shows the blocks of a snippet, as they are when the text or page is shown,
+/// so that changing the snippet changes it everywhere it is used.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Snippet {
+    pub id: String,
+}
+
+impl Snippet {
+    pub fn uuid(&self) -> Option<Uuid> {
+        Uuid::from_str(self.id.trim()).ok()
+    }
+}
+
+#[rocket::async_trait]
+impl BlockKind for Snippet {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Snippet",
+            label: "Gemensamt block",
+            fields: vec![BlockField {
+                name: "id",
+                label: Some("Det gemensamma blockets ID (från listan över gemensamma block):"),
+                placeholder: "Skriv det gemensamma blockets id här",
+                input: FieldInput::Text,
+            }],
+        }
+    }
+
+    fn needs(&self, needs: &mut RenderNeeds) {
+        if let Some(id) = self.uuid() {
+            needs.snippet(id);
+        }
+    }
+
+    /// Renders nothing if the snippet has been deleted, so that readers never see a broken block.
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        let Some(snippet) = self.uuid().and_then(|id| context.snippet(id)) else {
+            return Ok(String::new());
+        };
+
+        let rendered = context.render_snippet(&snippet.text_body);
+        if rendered.is_empty() {
+            return Ok(rendered);
+        }
+        Ok(format!(r#"<div class="snippet">{}</div>"#, rendered))
+    }
+
+    async fn validate(&self, db: &DatabaseHandler, errors: &mut FieldErrors) {
+        let Some(id) = self.uuid() else {
+            errors.add("id", "Skriv ett giltigt ID.");
+            return;
+        };
+
+        if SnippetData::get_by_id(db, id).await.is_err() {
+            errors.add("id", "Det finns inget gemensamt block med detta ID.");
+        }
+    }
+
+    /// A snippet is not part of the text itself, so it is left out of search and exports.
+    fn plain_text(&self) -> String {
+        String::new()
+    }
+
+    /// Stores the id the way `Snippet::usage` and the poll queries look for it.
+    fn sanitize(&mut self) {
+        if let Some(id) = self.uuid() {
+            self.id = id.to_string();
+        }
+    }
+}
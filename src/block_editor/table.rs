@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::DatabaseHandler,
+    defaults::{BLOCK_LONG_FIELD_MAX_CHARS, BLOCK_SHORT_FIELD_MAX_CHARS},
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, SelectOption, html, markdown,
+    render::RenderContext, validation::FieldErrors,
+};
+
+/// Whether the first row of a table holds the headings of the columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum TableHeader {
+    #[default]
+    FirstRow,
+    None,
+}
+
+/// How the cells of a column are aligned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl ColumnAlignment {
+    /// Parses the Swedish or English name of an alignment, or its first letter.
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "v" | "vänster" | "l" | "left" => Some(Self::Left),
+            "c" | "center" | "mitten" | "m" => Some(Self::Center),
+            "h" | "höger" | "r" | "right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+        }
+    }
+}
+
+/// A table, e.g. election results or budget figures, created by pasting CSV or TSV from a spreadsheet.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Table {
+    /// The rows of the table as CSV or TSV, see `Table::rows`.
+    pub data: String,
+    #[serde(default)]
+    pub header: TableHeader,
+    /// The alignment of each column separated by commas, e.g. `vänster, höger`.
+    /// Columns that are left out are aligned to the right if they only hold numbers, and to the left otherwise.
+    pub alignment: String,
+    pub caption: String,
+    pub source: String,
+}
+
+impl Table {
+    /// Parses `data` into rows of cells.
+    /// Cells are separated by tabs if there are any, otherwise by semicolons or commas, whichever is most common.
+    /// Cells may be quoted with `"`, and `""` is a quote inside a quoted cell.
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        let separator = if self.data.contains('\t') {
+            '\t'
+        } else if self.data.matches(';').count() > self.data.matches(',').count() {
+            ';'
+        } else {
+            ','
+        };
+
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut cell = String::new();
+        let mut in_quotes = false;
+        let mut chars = self.data.trim_matches(['\n', '\r']).chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    cell.push('"');
+                    chars.next();
+                }
+                '"' if in_quotes => in_quotes = false,
+                '"' if cell.trim().is_empty() => {
+                    cell.clear();
+                    in_quotes = true;
+                }
+                '\r' if !in_quotes => {}
+                '\n' if !in_quotes => {
+                    row.push(cell.trim().to_string());
+                    rows.push(row);
+                    row = Vec::new();
+                    cell = String::new();
+                }
+                c if c == separator && !in_quotes => {
+                    row.push(cell.trim().to_string());
+                    cell = String::new();
+                }
+                c => cell.push(c),
+            }
+        }
+        row.push(cell.trim().to_string());
+        rows.push(row);
+
+        rows.retain(|row| row.iter().any(|cell| !cell.is_empty()));
+        rows
+    }
+
+    /// Splits the rows into the header row, if there is one, and the other rows, and makes every row as wide as the widest.
+    /// Also gets the alignment of every column.
+    fn layout(&self) -> (Option<Vec<String>>, Vec<Vec<String>>, Vec<ColumnAlignment>) {
+        let mut rows = self.rows();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+        for row in rows.iter_mut() {
+            row.resize(columns, String::new());
+        }
+
+        let (header, body) = match self.header {
+            TableHeader::FirstRow if !rows.is_empty() => {
+                let body = rows.split_off(1);
+                (rows.pop(), body)
+            }
+            _ => (None, rows),
+        };
+
+        let given_alignments = self.alignments().unwrap_or_default();
+        let alignments = (0..columns)
+            .map(|column| {
+                given_alignments.get(column).copied().unwrap_or_else(|| {
+                    let mut cells = body
+                        .iter()
+                        .map(|row| row[column].as_str())
+                        .filter(|cell| !cell.is_empty())
+                        .peekable();
+                    if cells.peek().is_some() && cells.all(is_numeric) {
+                        ColumnAlignment::Right
+                    } else {
+                        ColumnAlignment::Left
+                    }
+                })
+            })
+            .collect();
+
+        (header, body, alignments)
+    }
+
+    /// The alignments given in `alignment`, or `Err` with the first one that is not an alignment.
+    fn alignments(&self) -> Result<Vec<ColumnAlignment>, &str> {
+        if self.alignment.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.alignment
+            .split(',')
+            .map(|name| ColumnAlignment::parse(name).ok_or(name.trim()))
+            .collect()
+    }
+}
+
+/// Whether the cell looks like a number, e.g. `12 345`, `−3,5` or `42 %`.
+fn is_numeric(cell: &str) -> bool {
+    cell.chars().any(|c| c.is_ascii_digit())
+        && cell
+            .chars()
+            .all(|c| c.is_ascii_digit() || " \u{a0},.:+-−%".contains(c))
+}
+
+#[rocket::async_trait]
+impl BlockKind for Table {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "Table",
+            label: "Tabell",
+            fields: vec![
+                BlockField {
+                    name: "caption",
+                    label: Some("Rubrik:"),
+                    placeholder: "Vad visar tabellen?",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "data",
+                    label: Some("Innehåll (klistra in från ett kalkylark, eller skriv CSV):"),
+                    placeholder: "Parti,Röster,Andel\nParti A,12 345,\"30,5 %\"",
+                    input: FieldInput::TextArea,
+                },
+                BlockField {
+                    name: "header",
+                    label: Some("Rubrikrad:"),
+                    placeholder: "",
+                    input: FieldInput::Select {
+                        options: vec![
+                            SelectOption {
+                                value: "FirstRow",
+                                label: "Första raden är kolumnrubriker",
+                            },
+                            SelectOption {
+                                value: "None",
+                                label: "Ingen rubrikrad",
+                            },
+                        ],
+                    },
+                },
+                BlockField {
+                    name: "alignment",
+                    label: Some("Kolumnernas justering:"),
+                    placeholder: "T.ex. vänster, höger, center. Tomt: tal till höger, text till vänster",
+                    input: FieldInput::Text,
+                },
+                BlockField {
+                    name: "source",
+                    label: Some("Källa:"),
+                    placeholder: "T.ex. Valmyndigheten",
+                    input: FieldInput::Text,
+                },
+            ],
+        }
+    }
+
+    fn render(&self, _context: &RenderContext) -> Result<String, Error> {
+        let (header, body, alignments) = self.layout();
+
+        // The alignment is set inline so that it is kept in feed readers, which do not have our stylesheet.
+        let render_row = |row: &[String], cell_tag: &str, scope: &str| {
+            format!(
+                "<tr>{}</tr>",
+                row.iter()
+                    .zip(&alignments)
+                    .map(|(cell, alignment)| format!(
+                        r#"<{cell_tag}{scope} style="text-align: {}">{}</{cell_tag}>"#,
+                        alignment.as_str(),
+                        html::escape(cell),
+                    ))
+                    .collect::<String>()
+            )
+        };
+
+        Ok(format!(
+            r#"<figure class="table-block"><table>{}{}<tbody>{}</tbody></table>{}</figure>"#,
+            if self.caption.trim().is_empty() {
+                String::new()
+            } else {
+                format!("<caption>{}</caption>", html::escape(self.caption.trim()))
+            },
+            header
+                .map(|header| format!(
+                    "<thead>{}</thead>",
+                    render_row(&header, "th", r#" scope="col""#)
+                ))
+                .unwrap_or_default(),
+            body.iter()
+                .map(|row| render_row(row, "td", ""))
+                .collect::<String>(),
+            if self.source.trim().is_empty() {
+                String::new()
+            } else {
+                format!(
+                    r#"<figcaption class="caption">Källa: {}</figcaption>"#,
+                    html::escape(self.source.trim())
+                )
+            },
+        ))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors
+            .required("data", &self.data)
+            .max_length("data", &self.data, BLOCK_LONG_FIELD_MAX_CHARS)
+            .max_length("caption", &self.caption, BLOCK_SHORT_FIELD_MAX_CHARS)
+            .max_length("source", &self.source, BLOCK_SHORT_FIELD_MAX_CHARS);
+
+        if let Err(name) = self.alignments() {
+            errors.add(
+                "alignment",
+                &format!(
+                    "\"{}\" är ingen justering. Skriv vänster, center eller höger för varje kolumn, separerade med komma.",
+                    name
+                ),
+            );
+        }
+    }
+
+    /// A GitHub flavored Markdown table, with the caption before it and the source after it.
+    fn markdown(&self) -> String {
+        let (header, body, alignments) = self.layout();
+        let render_row = |row: &[String]| {
+            format!(
+                "| {} |",
+                row.iter()
+                    .map(|cell| markdown::escape(cell).replace('|', "\\|"))
+                    .collect::<Vec<String>>()
+                    .join(" | ")
+            )
+        };
+
+        let mut lines = Vec::new();
+        if !self.caption.trim().is_empty() {
+            lines.push(format!("**{}**", markdown::escape(self.caption.trim())));
+            lines.push(String::new());
+        }
+        // Markdown tables must have a header row, so tables without one get an empty one.
+        lines.push(render_row(
+            &header.unwrap_or_else(|| vec![String::new(); alignments.len()]),
+        ));
+        lines.push(format!(
+            "|{}|",
+            alignments
+                .iter()
+                .map(|alignment| match alignment {
+                    ColumnAlignment::Left => " :--- ",
+                    ColumnAlignment::Center => " :---: ",
+                    ColumnAlignment::Right => " ---: ",
+                })
+                .collect::<Vec<&str>>()
+                .join("|")
+        ));
+        lines.extend(body.iter().map(|row| render_row(row)));
+        if !self.source.trim().is_empty() {
+            lines.push(String::new());
+            lines.push(format!("Källa: {}", markdown::escape(self.source.trim())));
+        }
+
+        lines.join("\n")
+    }
+
+    fn plain_text(&self) -> String {
+        [self.caption.clone()]
+            .into_iter()
+            .chain(self.rows().into_iter().map(|row| row.join(" ")))
+            .chain([self.source.clone()])
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that CSV and TSV are parsed with the right separator, and that quoted cells are kept whole.
+    #[test]
+    fn test_rows() {
+        let rows = |data: &str| {
+            Table {
+                data: data.to_string(),
+                header: TableHeader::FirstRow,
+                alignment: String::new(),
+                caption: String::new(),
+                source: String::new(),
+            }
+            .rows()
+        };
+
+        assert_eq!(
+            rows("Parti,Röster,Andel\r\nA,\"12,5\",\"Sa \"\"hej\"\"\"\n\nB,3\n"),
+            vec![
+                vec!["Parti", "Röster", "Andel"],
+                vec!["A", "12,5", r#"Sa "hej""#],
+                vec!["B", "3"],
+            ]
+        );
+        assert_eq!(
+            rows("Parti\tAndel\nA\t30,5 %"),
+            vec![vec!["Parti", "Andel"], vec!["A", "30,5 %"]]
+        );
+        assert_eq!(
+            rows("Parti;Andel\nA;30,5"),
+            vec![vec!["Parti", "Andel"], vec!["A", "30,5"]]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Anchors for headings, and the table of contents of long texts and pages built from them.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use slug::slugify;
+
+use crate::defaults::TABLE_OF_CONTENTS_MIN_HEADINGS;
+
+use super::{Block, heading::Heading};
+
+/// Ids that are used elsewhere in a text, e.g. by `footnotes`, so that headings cannot have them.
+fn is_reserved(anchor: &str) -> bool {
+    let is_number = |number: &str| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+
+    matches!(anchor, "innehall" | "kallor")
+        || anchor
+            .strip_prefix("kalla-ref-")
+            .or_else(|| anchor.strip_prefix("kalla-"))
+            .is_some_and(is_number)
+}
+
+/// Gives the headings of a text anchors from their text, e.g. `#katter-och-hundar`,
+/// so that links to a heading keep working as long as it is not renamed.
+/// Headings with the same text get `-2`, `-3` and so on, in the order they appear.
+#[derive(Debug, Default)]
+pub struct Anchors {
+    used: HashSet<String>,
+}
+
+impl Anchors {
+    pub fn anchor(&mut self, heading: &str) -> String {
+        let slug = match slugify(heading) {
+            slug if slug.is_empty() => "rubrik".to_string(),
+            slug => slug,
+        };
+
+        let mut anchor = slug.clone();
+        let mut number = 1;
+        while is_reserved(&anchor) || !self.used.insert(anchor.clone()) {
+            number += 1;
+            anchor = format!("{}-{}", slug, number);
+        }
+        anchor
+    }
+}
+
+/// A heading in the table of contents.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TableOfContentsEntry {
+    /// 2 to 4, as in `<h2>` to `<h4>`.
+    pub level: u8,
+    pub heading: String,
+    pub anchor: String,
+}
+
+/// The headings of a text or page, with the same anchors as when the blocks are rendered.
+/// Empty if there are fewer than `TABLE_OF_CONTENTS_MIN_HEADINGS` headings, since short texts do not need one.
+pub fn table_of_contents(blocks: &[Block]) -> Vec<TableOfContentsEntry> {
+    let mut anchors = Anchors::default();
+    let entries = blocks
+        .iter()
+        .filter_map(|block| block.downcast_ref::<Heading>())
+        .map(|heading| TableOfContentsEntry {
+            level: heading.level.number(),
+            heading: heading.heading.trim().to_string(),
+            anchor: anchors.anchor(&heading.heading),
+        })
+        .collect::<Vec<TableOfContentsEntry>>();
+
+    if entries.len() < TABLE_OF_CONTENTS_MIN_HEADINGS {
+        return Vec::new();
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that anchors are stable slugs, and that repeated or reserved ones get a number.
+    #[test]
+    fn test_anchors() {
+        let mut anchors = Anchors::default();
+        assert_eq!(anchors.anchor("Katter & hundar"), "katter-hundar");
+        assert_eq!(anchors.anchor("Katter & hundar"), "katter-hundar-2");
+        assert_eq!(anchors.anchor("Källor"), "kallor-2");
+        assert_eq!(anchors.anchor("Källa 1"), "kalla-1-2");
+        assert_eq!(anchors.anchor("Källa"), "kalla");
+        assert_eq!(anchors.anchor("???"), "rubrik");
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, defaults::BLOCK_LONG_FIELD_MAX_CHARS, error::Error};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, SelectOption, footnotes, render::RenderContext,
+    validation::FieldErrors,
+};
+
+/// Different colors that can be used for the text box.
+/// These reflect the colors in our graphical profile.
+///
+/// The color names should be self explanatory.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum TextBoxColor {
+    Grey,
+    Blue,
+    Green,
+    Red,
+    Yellow,
+}
+
+impl TextBoxColor {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TextBoxColor::Grey => "grey",
+            TextBoxColor::Blue => "blue",
+            TextBoxColor::Green => "green",
+            TextBoxColor::Red => "red",
+            TextBoxColor::Yellow => "yellow",
+        }
+    }
+}
+
+/// A text box; a paragraph with a background color plate.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TextBox {
+    pub text: String,
+    pub color: Option<TextBoxColor>,
+}
+
+#[rocket::async_trait]
+impl BlockKind for TextBox {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "TextBox",
+            label: "Textruta / faktaruta",
+            fields: vec![
+                BlockField {
+                    name: "text",
+                    label: Some("Text:"),
+                    placeholder: "",
+                    input: FieldInput::TextArea,
+                },
+                BlockField {
+                    name: "color",
+                    label: Some("Färg:"),
+                    placeholder: "",
+                    input: FieldInput::Select {
+                        options: vec![
+                            SelectOption {
+                                value: "Grey",
+                                label: "Grå",
+                            },
+                            SelectOption {
+                                value: "Green",
+                                label: "Grön",
+                            },
+                            SelectOption {
+                                value: "Yellow",
+                                label: "Gul",
+                            },
+                            SelectOption {
+                                value: "Red",
+                                label: "Röd",
+                            },
+                            SelectOption {
+                                value: "Blue",
+                                label: "Blå",
+                            },
+                        ],
+                    },
+                },
+            ],
+        }
+    }
+
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        Ok(format!(
+            "<div class=\"textbox {}\">{}</div>",
+            self.color
+                .as_ref()
+                .map(|color| color.as_str())
+                .unwrap_or(""),
+            footnotes::render_markdown(&self.text, context)
+        ))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        errors.required("text", &self.text).max_length(
+            "text",
+            &self.text,
+            BLOCK_LONG_FIELD_MAX_CHARS,
+        );
+        footnotes::validate("text", &self.text, errors);
+    }
+
+    fn plain_text(&self) -> String {
+        footnotes::plain_text(&self.text)
+    }
+
+    fn markdown(&self) -> String {
+        self.text.trim().to_string()
+    }
+}
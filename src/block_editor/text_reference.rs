@@ -0,0 +1,156 @@
+use std::{env, sync::LazyLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{
+        DatabaseHandler,
+        models::{article::Text, text_status::TextStatus},
+    },
+    error::Error,
+};
+
+use super::{
+    BlockField, BlockKind, BlockSchema, FieldInput, html,
+    render::{RenderContext, RenderNeeds},
+    validation::FieldErrors,
+};
+
+/// Matches the links to texts on the site, e.g. `https://example.com/t/<id>/<title>`, or just the id.
+static TEXT_URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*/t/)?(?<id>[0-9]+)([/?#].*)?$").unwrap());
+
+/// A "Läs också:" card that links to another text.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TextReference {
+    /// The id of the text, or a link to it.
+    pub text_id: String,
+}
+
+impl TextReference {
+    /// Gets the id of the text from either the id itself or a link to the text, e.g. `https://example.com/t/<id>/<title>`.
+    pub fn id(&self) -> Option<i32> {
+        TEXT_URL_RE
+            .captures(self.text_id.trim())
+            .and_then(|captures| captures["id"].parse().ok())
+    }
+}
+
+#[rocket::async_trait]
+impl BlockKind for TextReference {
+    fn schema() -> BlockSchema {
+        BlockSchema {
+            type_name: "TextReference",
+            label: "Läs också",
+            fields: vec![BlockField {
+                name: "text_id",
+                label: Some("Länk till texten, eller dess ID:"),
+                placeholder: "T.ex. https://example.com/t/12/titel",
+                input: FieldInput::Text,
+            }],
+        }
+    }
+
+    fn needs(&self, needs: &mut RenderNeeds) {
+        if let Some(id) = self.id() {
+            needs.text(id);
+        }
+    }
+
+    /// Renders nothing if the text is not published (any more), so that readers never see a broken card.
+    fn render(&self, context: &RenderContext) -> Result<String, Error> {
+        let Some(text) = self.id().and_then(|id| context.published_text(id)) else {
+            return Ok(String::new());
+        };
+
+        Ok(format!(
+            r#"<aside class="text-reference"><a href="{}">{}<div><p class="label">Läs också:</p><p class="title">{}</p><p>{}</p></div></a></aside>"#,
+            html::escape(&context.url(&format!("/t/{}/{}", text.id, text.title_slug))),
+            text.thumbnail
+                .as_ref()
+                .map(|thumbnail| format!(
+                    r#"<img src="{}" alt="{}" loading="lazy">"#,
+                    context.url(&format!("/dynamic-data/images/s/{}.webp", thumbnail.id)),
+                    html::escape(thumbnail.description.as_deref().unwrap_or_default())
+                ))
+                .unwrap_or_default(),
+            html::escape(&text.title),
+            html::escape(&text.lead_paragraph),
+        ))
+    }
+
+    async fn validate(&self, _db: &DatabaseHandler, errors: &mut FieldErrors) {
+        if self.id().is_none() {
+            errors.add(
+                "text_id",
+                "Skriv ID:t för texten, eller klistra in en länk till den.",
+            );
+        }
+    }
+
+    async fn warn(&self, db: &DatabaseHandler, warnings: &mut FieldErrors) {
+        let Some(id) = self.id() else {
+            return;
+        };
+
+        match Text::get_by_id(db, id, false).await {
+            Ok(text) if text.status == TextStatus::Published => {}
+            Ok(text) => {
+                warnings.add(
+                    "text_id",
+                    &format!(
+                        "\"{}\" är inte publicerad, så blocket visas inte för läsarna.",
+                        text.title
+                    ),
+                );
+            }
+            Err(_) => {
+                warnings.add(
+                    "text_id",
+                    &format!(
+                        "Det finns ingen text med ID {} (den kan ha raderats), så blocket visas inte för läsarna.",
+                        id
+                    ),
+                );
+            }
+        }
+    }
+
+    fn plain_text(&self) -> String {
+        String::new()
+    }
+
+    fn markdown(&self) -> String {
+        match self.id() {
+            Some(id) => format!(
+                "Läs också: <{}/t/{}>",
+                env::var("SITE_URL").unwrap_or_default(),
+                id
+            ),
+            None => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that the id is found both on its own and in links to the text.
+    #[test]
+    fn test_id() {
+        let id = |text_id: &str| {
+            TextReference {
+                text_id: text_id.to_string(),
+            }
+            .id()
+        };
+
+        assert_eq!(id(" 12 "), Some(12));
+        assert_eq!(id("https://example.com/t/12/en-titel"), Some(12));
+        assert_eq!(id("/t/12"), Some(12));
+        assert_eq!(id("https://example.com/p/12"), None);
+        assert_eq!(id("tolv"), None);
+    }
+}
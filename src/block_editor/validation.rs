@@ -0,0 +1,131 @@
+use serde::Serialize;
+
+use crate::database::DatabaseHandler;
+
+use super::Block;
+
+/// Something wrong with a field of a block, found by `BlockKind::validate`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    /// The name of the field, see `BlockField::name`.
+    pub field: &'static str,
+    /// Shown to the creator in the editor.
+    pub message: String,
+}
+
+/// Collects the `FieldError`s of a block.
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &'static str, message: &str) -> &mut Self {
+        self.0.push(FieldError {
+            field,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    /// The field must not be empty or only whitespace.
+    pub fn required(&mut self, field: &'static str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.add(field, "Fältet får inte vara tomt.");
+        }
+        self
+    }
+
+    /// The field may be at most `max_chars` characters long.
+    pub fn max_length(&mut self, field: &'static str, value: &str, max_chars: usize) -> &mut Self {
+        if value.chars().count() > max_chars {
+            self.add(
+                field,
+                &format!("Fältet får vara högst {} tecken långt.", max_chars),
+            );
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Something wrong with a block in a text or page, or a warning about it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockError {
+    /// The index of the block in the text or page.
+    pub index: usize,
+    pub block_type: &'static str,
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Turns the `FieldErrors` of the block at `index` into `BlockError`s.
+fn to_block_errors(index: usize, block: &Block, errors: FieldErrors) -> Vec<BlockError> {
+    errors
+        .0
+        .into_iter()
+        .map(|error| BlockError {
+            index,
+            block_type: block.type_name(),
+            field: error.field,
+            message: error.message,
+        })
+        .collect()
+}
+
+/// Checks every block before a text or page is saved, and lists everything that is wrong.
+pub async fn validate_blocks(
+    db: &DatabaseHandler,
+    blocks: &[Block],
+) -> Result<(), Vec<BlockError>> {
+    let mut block_errors = Vec::new();
+
+    for (index, block) in blocks.iter().enumerate() {
+        let mut errors = FieldErrors::default();
+        block.0.validate(db, &mut errors).await;
+        block_errors.extend(to_block_errors(index, block, errors));
+    }
+
+    match block_errors.is_empty() {
+        true => Ok(()),
+        false => Err(block_errors),
+    }
+}
+
+/// Lists everything the creator should know about the blocks, but that does not stop them from being saved,
+/// see `BlockKind::warn`.
+pub async fn warn_blocks(db: &DatabaseHandler, blocks: &[Block]) -> Vec<BlockError> {
+    let mut block_warnings = Vec::new();
+
+    for (index, block) in blocks.iter().enumerate() {
+        let mut warnings = FieldErrors::default();
+        block.0.warn(db, &mut warnings).await;
+        block_warnings.extend(to_block_errors(index, block, warnings));
+    }
+
+    block_warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that the field checks only complain about invalid values.
+    #[test]
+    fn test_field_errors() {
+        let mut errors = FieldErrors::default();
+        errors
+            .required("heading", "Rubrik")
+            .max_length("heading", "Rubrik", 6);
+        assert!(errors.is_empty());
+
+        errors
+            .required("heading", "  ")
+            .max_length("caption", "Bildtext", 3);
+        assert_eq!(
+            errors.0.iter().map(|error| error.field).collect::<Vec<_>>(),
+            ["heading", "caption"]
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+use super::{Block, embed::Embed};
+
+/// The block that embedded YouTube videos before the `Embed` block.
+/// It is only kept so that texts stored with it can still be loaded, as an `Embed` block.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct YouTube {
+    pub video_link: String,
+    pub caption: Option<String>,
+}
+
+impl From<YouTube> for Block {
+    fn from(youtube: YouTube) -> Self {
+        Block::new(Embed {
+            link: youtube.video_link,
+            caption: youtube.caption,
+        })
+    }
+}
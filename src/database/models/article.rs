@@ -7,9 +7,21 @@ use slug::slugify;
 use sqlx::{self, postgres::PgQueryResult, types::Json};
 use uuid::Uuid;
 
-use crate::{block_editor::Block, database::DatabaseHandler, error::Error};
-
-use super::{creator::Creator, image::Image};
+use crate::{
+    block_editor::{
+        Block,
+        render::{RenderContext, RenderTarget, render_blocks},
+    },
+    database::DatabaseHandler,
+    error::Error,
+};
+
+use super::{
+    creator::Creator,
+    image::Image,
+    revision::TextRevision,
+    text_status::{StatusTransition, TextStatus},
+};
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, sqlx::Type, FromFormField)]
 #[sqlx(type_name = "text_type", rename_all = "lowercase")]
@@ -52,9 +64,15 @@ pub struct Text {
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
     pub tags: Vec<String>,
-    pub is_published: bool,
-    pub marked_as_done: bool,
+    /// Where in the editorial workflow the text is, see `TextStatus`.
+    pub status: TextStatus,
+    /// If set, the text is automatically published once this time has passed.
+    pub publish_at: Option<DateTime<Local>>,
+    /// Usernames of the creators who wrote the text together with `author`, in byline order.
+    pub co_authors: Vec<String>,
     pub creator: Creator,
+    /// The creators of `co_authors`, in byline order.
+    pub co_creators: Vec<Creator>,
     pub thumbnail: Option<Image>,
 }
 
@@ -72,10 +90,12 @@ impl Default for Text {
             created_at: Local::now(),
             updated_at: Local::now(),
             tags: Vec::new(),
-            is_published: false,
-            marked_as_done: false,
+            status: TextStatus::Draft,
+            publish_at: None,
+            co_authors: Vec::new(),
             creator: Creator::create("Missing name", "Missing Display name", "password", false)
                 .unwrap(),
+            co_creators: Vec::new(),
             thumbnail: None,
         }
     }
@@ -83,9 +103,11 @@ impl Default for Text {
 
 impl Text {
     /// Create a new `Text`; this should be preferred over manually creating a new `Text`.
+    /// * `co_authors` usernames of the other creators in the byline, in order.
     pub fn create(
         title: &str,
         author: &str,
+        co_authors: Vec<String>,
         lead_paragraph: &str,
         text_body: Vec<Block>,
         text_type: TextType,
@@ -94,6 +116,7 @@ impl Text {
         Self {
             title: title.into(),
             author: author.into(),
+            co_authors,
             lead_paragraph: lead_paragraph.into(),
             text_body: Json(text_body),
             text_type,
@@ -102,9 +125,16 @@ impl Text {
         }
     }
 
+    /// The body as plain text, which is stored for search.
+    fn body_text(blocks: &[Block]) -> String {
+        render_blocks(&RenderContext::new(RenderTarget::PlainText), blocks)
+    }
+
     /// Saves an instance of `Text` to the database.
+    /// The saved text is also stored as its first revision, in the same transaction.
     pub async fn save_to_db(&self, db: &DatabaseHandler) -> Result<Text, Error> {
-        sqlx::query_file_as!(
+        let mut transaction = db.pool.begin().await?;
+        let text = sqlx::query_file_as!(
             Self,
             "sql/articles/insert.sql",
             self.title,
@@ -115,18 +145,29 @@ impl Text {
             serde_json::to_value(self.text_body.clone())?,
             &self.text_type as &TextType,
             &self.tags,
-            self.is_published,
-            self.marked_as_done,
+            &self.status as &TextStatus,
+            &self.co_authors,
+            Self::body_text(&self.text_body),
         )
-        .fetch_one(&db.pool)
-        .await
-        .map_err(Error::from)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        TextRevision::save_from_text(&mut *transaction, &text, &text.author).await?;
+        transaction.commit().await?;
+
+        Ok(text)
     }
 
     /// Updates ONE text from the data by its `id`.
+    /// The updated text is also stored as a new revision, in the same transaction.
+    /// * `editor` is the username of the creator who saved the text.
+    /// * `loaded_updated_at` the `updated_at` of the text when the editor loaded it.
+    ///   If the text has been updated since, nothing is saved and a `409 Conflict` error is returned.
+    ///   `None` skips this check.
     #[allow(clippy::too_many_arguments)]
     pub async fn update_by_id(
         db: &DatabaseHandler,
+        editor: &str,
         id: i32,
         title: &str,
         thumbnail_id: Option<Uuid>,
@@ -134,8 +175,12 @@ impl Text {
         text_body: Json<Vec<Block>>,
         text_type: TextType,
         tags: &Vec<String>,
+        co_authors: &[String],
+        loaded_updated_at: Option<DateTime<Local>>,
     ) -> Result<Text, Error> {
-        sqlx::query_file_as!(
+        let body_text = Self::body_text(&text_body);
+        let mut transaction = db.pool.begin().await?;
+        let text = sqlx::query_file_as!(
             Self,
             "sql/articles/update.sql",
             title,
@@ -146,10 +191,22 @@ impl Text {
             text_type as TextType,
             tags,
             id,
+            co_authors,
+            loaded_updated_at,
+            body_text,
         )
-        .fetch_one(&db.pool)
-        .await
-        .map_err(Error::from)
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "The text has been changed by someone else since it was loaded!",
+            Status::Conflict,
+        ))?;
+
+        TextRevision::save_from_text(&mut *transaction, &text, editor).await?;
+        transaction.commit().await?;
+
+        Ok(text)
     }
 
     /// Gets up to `n` latest `Text`s from the database.
@@ -164,17 +221,32 @@ impl Text {
             .map_err(Error::from)
     }
 
-    /// Gets all done, but unpublished articles.
-    pub async fn get_all_done_unpublished(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
-        sqlx::query_file_as!(Self, "sql/articles/get_all_done_unpublished.sql")
+    /// Gets up to `n` latest published `Text`s that have an `Audio` block, e.g. for the podcast feed.
+    pub async fn get_n_latest_with_audio(db: &DatabaseHandler, n: i64) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/articles/get_with_audio.sql", n)
             .fetch_all(&db.pool)
             .await
             .map_err(Error::from)
     }
 
-    /// Gets the count of unpublished articles.
-    pub async fn get_all_done_unpublished_count(db: &DatabaseHandler) -> Result<i64, Error> {
-        sqlx::query_file_scalar!("sql/articles/get_all_done_unpublished-count.sql")
+    /// Gets ALL `Text`s from the database in any of `statuses`, newest first.
+    pub async fn get_by_status(
+        db: &DatabaseHandler,
+        statuses: &[TextStatus],
+    ) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(
+            Self,
+            "sql/articles/get_by_status.sql",
+            statuses as &[TextStatus]
+        )
+        .fetch_all(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Gets the count of `Text`s in `status`.
+    pub async fn get_status_count(db: &DatabaseHandler, status: TextStatus) -> Result<i64, Error> {
+        sqlx::query_file_scalar!("sql/articles/get_status_count.sql", status as TextStatus)
             .fetch_one(&db.pool)
             .await
             .map_err(Error::from)
@@ -193,7 +265,20 @@ impl Text {
             .map_err(Error::from)
     }
 
-    /// Gets ALL `Text`s from the database by `author`.
+    /// Gets the `Text`s with any of `ids` in one go; ids of missing texts are left out.
+    /// * `must_be_published` if `true`, only published texts are returned.
+    pub async fn get_by_ids(
+        db: &DatabaseHandler,
+        ids: &[i32],
+        must_be_published: bool,
+    ) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/articles/get_by_ids.sql", ids, must_be_published)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ALL `Text`s from the database by `author`, including those `author` co-wrote.
     pub async fn get_by_author(
         db: &DatabaseHandler,
         author: &str,
@@ -257,58 +342,80 @@ impl Text {
             .map_err(Error::from)
     }
 
-    /// Changes the `is_published` field of a text in the database.
-    /// * `executor` is the person who wants to publish the text, must be a `Publisher`.
+    /// Checks if `username` is an author or co-author of the text.
+    pub fn is_author(&self, username: &str) -> bool {
+        self.author == username
+            || self
+                .co_authors
+                .iter()
+                .any(|co_author| co_author == username)
+    }
+
+    /// Moves a text to another state in the editorial workflow and records the transition.
+    /// The transition is validated by `TextStatus::check_transition`, and if the status changes
+    /// before it is saved, nothing is saved and a `409 Conflict` error is returned.
+    /// Any scheduled publishing is cleared, so that e.g. an unpublished text is not republished.
+    /// * `executor` is the person who changes the state.
     /// * `id` text's id.
-    /// * `status` publish status; `true` for published, `false` for not published.
-    pub async fn set_publish_status(
+    /// * `status` the new state.
+    pub async fn set_status(
         db: &DatabaseHandler,
         executor: &Creator,
         id: i32,
-        status: bool,
+        status: TextStatus,
     ) -> Result<(), Error> {
-        if !executor.is_publisher() {
-            return Err(Error::create(
-                &format!("{}:{}", file!(), line!()),
-                "Must be `Publisher` to set publish status!",
-                Status::Unauthorized,
-            ));
-        }
+        let text = Self::get_by_id(db, id, false).await?;
 
-        sqlx::query!(
-            "UPDATE articles SET is_published = $1 WHERE id = $2",
-            status,
-            id
+        text.status
+            .check_transition(status, executor, text.is_author(&executor.username))?;
+
+        sqlx::query_file!(
+            "sql/articles/set_status.sql",
+            id,
+            status as TextStatus,
+            text.status as TextStatus,
+            executor.username,
         )
-        .execute(&db.pool)
-        .await
+        .fetch_optional(&db.pool)
+        .await?
         .map(|_| ())
-        .map_err(Error::from)
+        .ok_or(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "The status of the text has been changed by someone else!",
+            Status::Conflict,
+        ))
     }
 
-    /// Changes the `marked_as_done` field of a text in the database.
-    /// * `executor` is the person who wants to publish the text, must be a `Publisher`.
+    /// Gets the history of state changes of a text, newest first.
+    pub async fn get_status_history(
+        db: &DatabaseHandler,
+        id: i32,
+    ) -> Result<Vec<StatusTransition>, Error> {
+        StatusTransition::get_by_text_id(db, id).await
+    }
+
+    /// Changes the `publish_at` field of a text in the database.
+    /// * `executor` is the person who wants to schedule the text, must be allowed to publish it.
     /// * `id` text's id.
-    /// * `status` done status; `true` for done, `false` for not done.
-    pub async fn set_done_status(
+    /// * `publish_at` when the text should be published; `None` cancels the scheduled publishing.
+    pub async fn set_publish_at(
         db: &DatabaseHandler,
         executor: &Creator,
         id: i32,
-        status: bool,
+        publish_at: Option<DateTime<Local>>,
     ) -> Result<(), Error> {
         let text = Self::get_by_id(db, id, false).await?;
 
-        if text.author != executor.username {
-            return Err(Error::create(
-                &format!("{}:{}", file!(), line!()),
-                "Only original author can change sdone status!",
-                Status::Unauthorized,
-            ));
-        }
+        // Scheduling is only possible if the executor could publish the text right now.
+        text.status.check_transition(
+            TextStatus::Published,
+            executor,
+            text.is_author(&executor.username),
+        )?;
 
         sqlx::query!(
-            "UPDATE articles SET marked_as_done = $1 WHERE id = $2",
-            status,
+            "UPDATE articles SET publish_at = $1 WHERE id = $2",
+            publish_at,
             id
         )
         .execute(&db.pool)
@@ -317,11 +424,71 @@ impl Text {
         .map_err(Error::from)
     }
 
-    /// Deletes ONE `Text` from the database by its id.
+    /// Publishes all approved or unpublished texts whose `publish_at` has passed.
+    /// The transitions are recorded without an actor.
+    /// Returns the ids of the texts that were published.
+    pub async fn publish_due(db: &DatabaseHandler) -> Result<Vec<i32>, Error> {
+        sqlx::query_file_scalar!("sql/articles/publish_due.sql")
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Renders the `body_text` of texts stored before there was one, which have the JSON of their blocks as it.
+    /// Returns the number of texts that were updated.
+    pub async fn fill_body_text(db: &DatabaseHandler) -> Result<usize, Error> {
+        let texts = sqlx::query!(
+            r#"SELECT id, text_body AS "text_body!: Json<Vec<Block>>" FROM articles WHERE body_text = text_body::text"#
+        )
+        .fetch_all(&db.pool)
+        .await?;
+
+        for text in &texts {
+            sqlx::query!(
+                "UPDATE articles SET body_text = $2 WHERE id = $1",
+                text.id,
+                Self::body_text(&text.text_body),
+            )
+            .execute(&db.pool)
+            .await?;
+        }
+
+        Ok(texts.len())
+    }
+
+    /// Moves ONE `Text` to the trash by its id.
+    /// Trashed texts are left out by all `get_*` functions, and are purged by `Text::purge_trashed`.
     pub async fn delete(db: &DatabaseHandler, id: i32) -> Result<PgQueryResult, Error> {
-        sqlx::query!("DELETE FROM articles WHERE id = $1", id)
+        sqlx::query!(
+            "UPDATE articles SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+            id
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Restores ONE trashed `Text` by its id.
+    pub async fn restore(db: &DatabaseHandler, id: i32) -> Result<PgQueryResult, Error> {
+        sqlx::query!(
+            "UPDATE articles SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+            id
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Permanently deletes ALL `Text`s that were trashed before `before`.
+    /// Returns the number of deleted texts.
+    pub async fn purge_trashed(
+        db: &DatabaseHandler,
+        before: DateTime<Local>,
+    ) -> Result<u64, Error> {
+        sqlx::query!("DELETE FROM articles WHERE deleted_at < $1", before)
             .execute(&db.pool)
             .await
+            .map(|result| result.rows_affected())
             .map_err(Error::from)
     }
 }
@@ -347,6 +514,7 @@ mod tests {
             let text = Text::create(
                 "Katter och hundar",
                 "sven.svensson",
+                Vec::new(),
                 "Lead paragraph",
                 Vec::new(),
                 TextType::Article,
@@ -0,0 +1,254 @@
+use std::{fs, path::Path};
+
+use chrono::{DateTime, Local};
+use rocket::http::Status;
+use rocket::tokio::task;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgQueryResult;
+use symphonia::core::{
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+use uuid::Uuid;
+
+use crate::{
+    database::DatabaseHandler,
+    defaults::{AUDIO_FORMATS, DATA_DIR},
+    error::Error,
+};
+
+/// `Audio` represents the metadata of an audio file, e.g. a podcast episode.
+/// It is stored in the database. The actual audio files are stored in:
+/// `${DATA_DIR}/audio/{id}.{extension}`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Audio {
+    pub id: Uuid,
+    /// The username of the creator who uploaded the file.
+    pub uploaded_by: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// One of the stored MIME types in `AUDIO_FORMATS`.
+    pub mime_type: String,
+    pub duration_secs: i32,
+    pub size_bytes: i64,
+    pub created_at: DateTime<Local>,
+}
+
+impl Audio {
+    /// Gets the stored MIME type and the file extension of an uploaded file by the MIME type the browser sent.
+    pub fn format(mime_type: &str) -> Option<(&'static str, &'static str)> {
+        AUDIO_FORMATS
+            .iter()
+            .find(|(uploaded, _, _)| *uploaded == mime_type)
+            .map(|(_, stored, extension)| (*stored, *extension))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        Self::format(&self.mime_type)
+            .map(|(_, extension)| extension)
+            .unwrap_or("bin")
+    }
+
+    /// The address of the file, relative to the site.
+    pub fn url(&self) -> String {
+        format!("/dynamic-data/audio/{}.{}", self.id, self.extension())
+    }
+
+    /// The duration as `H:MM:SS`, or `M:SS` if it is shorter than an hour.
+    pub fn formatted_duration(&self) -> String {
+        let (hours, minutes, seconds) = (
+            self.duration_secs / 3600,
+            self.duration_secs / 60 % 60,
+            self.duration_secs % 60,
+        );
+        match hours {
+            0 => format!("{}:{:02}", minutes, seconds),
+            _ => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+        }
+    }
+
+    /// Reads how long an audio file is, in whole seconds (rounded up).
+    /// Fails if the file is not audio in any of the `AUDIO_FORMATS`.
+    pub fn read_duration(path: &Path, extension: &str) -> Result<i32, Error> {
+        let not_audio = || {
+            Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "The file is not audio in any of the supported formats!",
+                Status::BadRequest,
+            )
+        };
+
+        let source = MediaSourceStream::new(Box::new(fs::File::open(path)?), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                source,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|_| not_audio())?
+            .format;
+
+        let track = format.default_track().ok_or_else(not_audio)?;
+        let track_id = track.id;
+        let time_base = track.codec_params.time_base.ok_or_else(not_audio)?;
+
+        // Not all files say how long they are, e.g. MP3s without a Xing header, so then every packet is counted.
+        let frames = match track.codec_params.n_frames {
+            Some(frames) => frames,
+            None => {
+                let mut frames = 0;
+                while let Ok(packet) = format.next_packet() {
+                    if packet.track_id() == track_id {
+                        frames = packet.ts() + packet.dur();
+                    }
+                }
+                frames
+            }
+        };
+
+        let time = time_base.calc_time(frames);
+        Ok(time.seconds as i32 + i32::from(time.frac > 0.0))
+    }
+
+    /// Saves the metadata of an audio file that has been stored at `Audio::path`, reading its duration and size.
+    /// The file is removed if it is not audio.
+    pub async fn save_to_db(
+        db: &DatabaseHandler,
+        id: Uuid,
+        uploaded_by: &str,
+        title: &str,
+        description: Option<&str>,
+        mime_type: &str,
+    ) -> Result<Self, Error> {
+        let extension = Self::format(mime_type)
+            .map(|(_, extension)| extension)
+            .unwrap_or("bin");
+        let path = Self::path(id, extension);
+        let read_path = path.clone();
+        let duration_secs =
+            task::spawn_blocking(move || Self::read_duration(Path::new(&read_path), extension))
+                .await
+                .map_err(|err| {
+                    Error::create(
+                        &format!("{}:{}", file!(), line!()),
+                        &format!("Failed to read the audio file: {}", err),
+                        Status::InternalServerError,
+                    )
+                })?;
+        let duration_secs = match duration_secs {
+            Ok(duration_secs) => duration_secs,
+            Err(err) => {
+                fs::remove_file(&path).ok();
+                return Err(err);
+            }
+        };
+        let size_bytes = fs::metadata(&path)?.len() as i64;
+
+        sqlx::query_file_as!(
+            Self,
+            "sql/audio/insert.sql",
+            id,
+            uploaded_by,
+            title,
+            description,
+            mime_type,
+            duration_secs,
+            size_bytes
+        )
+        .fetch_one(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// The path where the file of an audio is stored.
+    pub fn path(id: Uuid, extension: &str) -> String {
+        format!("{}/audio/{}.{}", DATA_DIR, id, extension)
+    }
+
+    /// Gets the `Audio`s with any of `ids` in one go; ids of missing or trashed audio are left out.
+    pub async fn get_by_ids(db: &DatabaseHandler, ids: &[Uuid]) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/audio/get_by_ids.sql", ids)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ALL `Audio`s from the database, newest first.
+    pub async fn get_all(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/audio/get_all.sql")
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Moves ONE `Audio` to the trash by its id; the file is kept until it is purged.
+    pub async fn delete(db: &DatabaseHandler, id: Uuid) -> Result<PgQueryResult, Error> {
+        sqlx::query!(
+            "UPDATE audio SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+            id
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Restores ONE trashed `Audio` by its id.
+    pub async fn restore(db: &DatabaseHandler, id: Uuid) -> Result<PgQueryResult, Error> {
+        sqlx::query!(
+            "UPDATE audio SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+            id
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Permanently deletes ALL `Audio`s (and their files) that were trashed before `before`.
+    /// Returns the number of deleted audio files.
+    pub async fn purge_trashed(
+        db: &DatabaseHandler,
+        before: DateTime<Local>,
+    ) -> Result<u64, Error> {
+        let purged = sqlx::query!(
+            "DELETE FROM audio WHERE deleted_at < $1 RETURNING id, mime_type",
+            before
+        )
+        .fetch_all(&db.pool)
+        .await?;
+
+        for audio in purged.iter() {
+            let extension = Self::format(&audio.mime_type)
+                .map(|(_, extension)| extension)
+                .unwrap_or("bin");
+            fs::remove_file(Self::path(audio.id, extension)).ok();
+        }
+
+        Ok(purged.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that durations are shown the way podcast apps expect them.
+    #[test]
+    fn test_formatted_duration() {
+        let audio = |duration_secs| Audio {
+            id: Uuid::nil(),
+            uploaded_by: String::new(),
+            title: String::new(),
+            description: None,
+            mime_type: "audio/mpeg".to_string(),
+            duration_secs,
+            size_bytes: 0,
+            created_at: Local::now(),
+        };
+
+        assert_eq!(audio(59).formatted_duration(), "0:59");
+        assert_eq!(audio(754).formatted_duration(), "12:34");
+        assert_eq!(audio(3725).formatted_duration(), "1:02:05");
+    }
+}
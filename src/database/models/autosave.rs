@@ -0,0 +1,101 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+
+use crate::{block_editor::Block, database::DatabaseHandler, error::Error};
+
+use super::article::{Text, TextType};
+
+/// An unsaved working copy of a `Text`, stored regularly by the editor so that nothing is lost if the browser crashes.
+/// Every creator has at most one working copy per text, and one for a text that has not been saved yet.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Autosave {
+    /// Username of the creator whose working copy this is.
+    pub creator: String,
+    /// The text being edited; `None` for a text that has not been saved yet.
+    pub text_id: Option<i32>,
+    pub title: String,
+    pub lead_paragraph: String,
+    pub text_body: Json<Vec<Block>>,
+    pub text_type: TextType,
+    pub tags: Vec<String>,
+    /// Usernames of the co-authors; they are only checked when the text is saved.
+    pub co_authors: Vec<String>,
+    /// The `updated_at` of the text when the working copy was started; `None` for a text that has not been saved yet.
+    pub base_updated_at: Option<DateTime<Local>>,
+    pub saved_at: DateTime<Local>,
+}
+
+impl Autosave {
+    /// Stores a working copy, replacing any earlier working copy of the same text by the same creator.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn save(
+        db: &DatabaseHandler,
+        creator: &str,
+        text_id: Option<i32>,
+        title: &str,
+        lead_paragraph: &str,
+        text_body: Vec<Block>,
+        text_type: TextType,
+        tags: &[String],
+        co_authors: &[String],
+        base_updated_at: Option<DateTime<Local>>,
+    ) -> Result<Self, Error> {
+        Ok(sqlx::query_file_as!(
+            Self,
+            "sql/autosaves/upsert.sql",
+            creator,
+            text_id,
+            title,
+            lead_paragraph,
+            serde_json::to_value(text_body)?,
+            text_type as TextType,
+            tags,
+            co_authors,
+            base_updated_at,
+        )
+        .fetch_one(&db.pool)
+        .await?)
+    }
+
+    /// Gets the working copy of a text by a creator, if there is one.
+    pub async fn get(
+        db: &DatabaseHandler,
+        creator: &str,
+        text_id: Option<i32>,
+    ) -> Result<Option<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/autosaves/get.sql", creator, text_id)
+            .fetch_optional(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Throws away the working copy of a text by a creator, e.g. when the text has been saved.
+    pub async fn discard(
+        db: &DatabaseHandler,
+        creator: &str,
+        text_id: Option<i32>,
+    ) -> Result<(), Error> {
+        sqlx::query_file!("sql/autosaves/delete.sql", creator, text_id)
+            .execute(&db.pool)
+            .await
+            .map(|_| ())
+            .map_err(Error::from)
+    }
+
+    /// Puts the working copy into `text`, so that it can be shown in the editor.
+    /// The text gets the `updated_at` the working copy was based on, so that saving it after someone else
+    /// has saved the text gives a `409 Conflict`.
+    pub fn recover_into(self, text: Text) -> Text {
+        Text {
+            title: self.title,
+            lead_paragraph: self.lead_paragraph,
+            text_body: self.text_body,
+            text_type: self.text_type,
+            tags: self.tags,
+            co_authors: self.co_authors,
+            updated_at: self.base_updated_at.unwrap_or(text.updated_at),
+            ..text
+        }
+    }
+}
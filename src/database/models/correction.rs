@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, error::Error};
+
+use super::{article::Text, creator::Creator, text_status::TextStatus};
+
+/// A public notice that a published `Text` has been corrected.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Correction {
+    pub id: i32,
+    pub text_id: i32,
+    /// What was corrected, shown to readers.
+    pub body: String,
+    /// Username of the publisher who added the correction.
+    pub author: String,
+    pub created_at: DateTime<Local>,
+    /// Title of the corrected text, used when linking to it.
+    pub text_title: String,
+    pub text_title_slug: String,
+}
+
+impl Correction {
+    /// Adds a correction notice to a published `Text` and bumps the text's `updated_at`.
+    /// * `executor` must be a publisher.
+    pub async fn add(
+        db: &DatabaseHandler,
+        executor: &Creator,
+        text_id: i32,
+        body: &str,
+    ) -> Result<Self, Error> {
+        if !executor.is_publisher() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Must be publisher to add a correction!",
+                Status::Unauthorized,
+            ));
+        }
+
+        if body.trim().is_empty() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "A correction cannot be empty!",
+                Status::BadRequest,
+            ));
+        }
+
+        let text = Text::get_by_id(db, text_id, false).await?;
+        if text.status != TextStatus::Published {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Only published texts can be corrected!",
+                Status::BadRequest,
+            ));
+        }
+
+        sqlx::query_file_as!(
+            Self,
+            "sql/text_corrections/insert.sql",
+            text_id,
+            body,
+            executor.username,
+        )
+        .fetch_one(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Gets ALL corrections of a `Text`, oldest first.
+    pub async fn get_by_text_id(db: &DatabaseHandler, text_id: i32) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/text_corrections/get_by_text_id.sql", text_id)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ALL corrections of each of the `Text`s with `text_ids` in one go, in the same order as `text_ids`.
+    pub async fn get_by_text_ids(
+        db: &DatabaseHandler,
+        text_ids: &[i32],
+    ) -> Result<Vec<Vec<Self>>, Error> {
+        let mut corrections_by_text_id: HashMap<i32, Vec<Self>> = HashMap::new();
+        for correction in
+            sqlx::query_file_as!(Self, "sql/text_corrections/get_by_text_ids.sql", text_ids)
+                .fetch_all(&db.pool)
+                .await?
+        {
+            corrections_by_text_id
+                .entry(correction.text_id)
+                .or_default()
+                .push(correction);
+        }
+
+        Ok(text_ids
+            .iter()
+            .map(|text_id| corrections_by_text_id.remove(text_id).unwrap_or_default())
+            .collect())
+    }
+
+    /// Gets ALL corrections of published `Text`s, newest first.
+    pub async fn get_all_published(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/text_corrections/get_all_published.sql")
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+}
@@ -9,9 +9,9 @@ use uuid::Uuid;
 use crate::{database::DatabaseHandler, defaults::DATA_DIR, error::Error};
 
 /// Max width of a small image.
-const IMG_S_SIZE: u32 = 600;
+pub const IMG_S_SIZE: u32 = 600;
 /// Max width of a medium image.
-const IMG_M_SIZE: u32 = 1200;
+pub const IMG_M_SIZE: u32 = 1200;
 
 /// `Image` represents the metadata of an image.
 /// It is stored in the database. The actual image files are stored in:
@@ -21,7 +21,7 @@ const IMG_M_SIZE: u32 = 1200;
 /// * `s` - 600
 /// * `m` - 1200
 /// * `l` - Original image size
-#[derive(Debug, Clone, Deserialize, Serialize, sqlx::Type)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, sqlx::Type)]
 pub struct Image {
     pub id: Uuid,
     pub author: String,
@@ -124,6 +124,14 @@ impl Image {
             .map_err(Error::from)
     }
 
+    /// Gets the `Image`s with any of `ids` in one go; ids of missing or trashed images are left out.
+    pub async fn get_by_ids(db: &DatabaseHandler, ids: &[Uuid]) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/images/get_by_ids.sql", ids)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
     /// Gets ALL `Image`s from the database.
     pub async fn get_all(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
         sqlx::query_file_as!(Self, "sql/images/get_all.sql")
@@ -159,17 +167,66 @@ impl Image {
             .map_err(Error::from)
     }
 
-    /// Deletes ONE `Image` from the database and file stystem by its id.
-    pub async fn delete(db: &DatabaseHandler, id: Uuid) -> Result<PgQueryResult, Error> {
-        // Remove all files.
+    /// Removes all files of an image.
+    fn remove_files(id: Uuid) {
         fs::remove_file(format!("{}/images/s/{}.webp", DATA_DIR, id)).ok();
         fs::remove_file(format!("{}/images/m/{}.webp", DATA_DIR, id)).ok();
         fs::remove_file(format!("{}/images/l/{}.webp", DATA_DIR, id)).ok();
+    }
+
+    /// Moves ONE `Image` to the trash by its id; the files are kept until it is purged.
+    /// Trashed images are left out by all `get_*` functions, and are purged by `Image::purge_trashed`.
+    pub async fn delete(db: &DatabaseHandler, id: Uuid) -> Result<PgQueryResult, Error> {
+        sqlx::query!(
+            "UPDATE images SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+            id
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Restores ONE trashed `Image` by its id.
+    pub async fn restore(db: &DatabaseHandler, id: Uuid) -> Result<PgQueryResult, Error> {
+        sqlx::query!(
+            "UPDATE images SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+            id
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Deletes ONE `Image` from the database and file stystem by its id, without going through the trash.
+    pub async fn delete_permanently(
+        db: &DatabaseHandler,
+        id: Uuid,
+    ) -> Result<PgQueryResult, Error> {
+        Self::remove_files(id);
 
-        // Remove from database.
         sqlx::query!("DELETE FROM images WHERE id = $1", id)
             .execute(&db.pool)
             .await
             .map_err(Error::from)
     }
+
+    /// Permanently deletes ALL `Image`s (and their files) that were trashed before `before`.
+    /// Returns the number of deleted images.
+    pub async fn purge_trashed(
+        db: &DatabaseHandler,
+        before: DateTime<Local>,
+    ) -> Result<u64, Error> {
+        let ids = sqlx::query_scalar!(
+            "DELETE FROM images WHERE deleted_at < $1 RETURNING id",
+            before
+        )
+        .fetch_all(&db.pool)
+        .await?;
+
+        for id in ids.iter() {
+            Self::remove_files(*id);
+        }
+
+        Ok(ids.len() as u64)
+    }
 }
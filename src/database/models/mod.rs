@@ -1,4 +1,14 @@
 pub mod article;
+pub mod audio;
+pub mod autosave;
+pub mod correction;
 pub mod creator;
 pub mod image;
 pub mod page;
+pub mod poll;
+pub mod preview_link;
+pub mod review_comment;
+pub mod revision;
+pub mod snippet;
+pub mod text_status;
+pub mod trash;
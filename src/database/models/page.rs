@@ -1,5 +1,7 @@
 use std::fmt::Debug;
 
+use chrono::{DateTime, Local};
+use rocket::http::Status;
 use serde::{Deserialize, Serialize};
 use sqlx::{self, postgres::PgQueryResult, types::Json};
 
@@ -10,6 +12,7 @@ pub struct Page {
     pub path: String,
     pub title: String,
     pub text_body: Json<Vec<Block>>,
+    pub updated_at: DateTime<Local>,
 }
 
 impl Default for Page {
@@ -18,6 +21,7 @@ impl Default for Page {
             path: "Missing path!".into(),
             title: "Missing title!".into(),
             text_body: Json(Vec::new()),
+            updated_at: Local::now(),
         }
     }
 }
@@ -29,6 +33,7 @@ impl Page {
             path: path.into(),
             title: title.into(),
             text_body: Json(text_body),
+            ..Default::default()
         }
     }
 
@@ -47,12 +52,16 @@ impl Page {
     }
 
     /// Updates ONE `Page` from the data by its `path`.
+    /// * `loaded_updated_at` the `updated_at` of the page when the editor loaded it.
+    ///   If the page has been updated since, nothing is saved and a `409 Conflict` error is returned.
+    ///   `None` skips this check.
     pub async fn update_by_path(
         db: &DatabaseHandler,
         old_path: &str,
         new_path: &str,
         title: &str,
         text_body: Json<Vec<Block>>,
+        loaded_updated_at: Option<DateTime<Local>>,
     ) -> Result<Page, Error> {
         sqlx::query_file_as!(
             Self,
@@ -61,10 +70,15 @@ impl Page {
             new_path,
             title,
             serde_json::to_value(text_body)?,
+            loaded_updated_at,
         )
-        .fetch_one(&db.pool)
-        .await
-        .map_err(Error::from)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "The page has been changed by someone else since it was loaded!",
+            Status::Conflict,
+        ))
     }
 
     /// Gets ONE `Page` from the database by its path.
@@ -83,11 +97,51 @@ impl Page {
             .map_err(Error::from)
     }
 
-    /// Deletes ONE `Text` from the database by its id.
-    pub async fn delete(db: &DatabaseHandler, id: i32) -> Result<PgQueryResult, Error> {
-        sqlx::query!("DELETE FROM articles WHERE id = $1", id)
+    /// Moves ONE `Page` to the trash by its path.
+    /// Trashed pages are left out by all `get_*` functions, and are purged by `Page::purge_trashed`.
+    pub async fn delete(db: &DatabaseHandler, path: &str) -> Result<PgQueryResult, Error> {
+        sqlx::query!(
+            "UPDATE pages SET deleted_at = NOW() WHERE path = $1 AND deleted_at IS NULL",
+            path
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Checks if the `Page` with the path is in the trash.
+    /// A trashed page keeps its path until it is purged, so no other page can use it until then.
+    pub async fn is_trashed(db: &DatabaseHandler, path: &str) -> Result<bool, Error> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM pages WHERE path = $1 AND deleted_at IS NOT NULL) AS "exists!""#,
+            path
+        )
+        .fetch_one(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Restores ONE trashed `Page` by its path.
+    pub async fn restore(db: &DatabaseHandler, path: &str) -> Result<PgQueryResult, Error> {
+        sqlx::query!(
+            "UPDATE pages SET deleted_at = NULL WHERE path = $1 AND deleted_at IS NOT NULL",
+            path
+        )
+        .execute(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Permanently deletes ALL `Page`s that were trashed before `before`.
+    /// Returns the number of deleted pages.
+    pub async fn purge_trashed(
+        db: &DatabaseHandler,
+        before: DateTime<Local>,
+    ) -> Result<u64, Error> {
+        sqlx::query!("DELETE FROM pages WHERE deleted_at < $1", before)
             .execute(&db.pool)
             .await
+            .map(|result| result.rows_affected())
             .map_err(Error::from)
     }
 }
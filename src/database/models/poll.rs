@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::{block_editor::poll::Poll, database::DatabaseHandler, error::Error};
+
+/// A vote by a reader in a `Poll`.
+/// Every browser can only vote once per poll, as identified by the random `voter` id in its cookie.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PollVote {
+    pub poll_id: Uuid,
+    /// The index of the chosen option, see `Poll::option_list`.
+    pub option_index: i32,
+    pub voter: Uuid,
+    pub voted_at: DateTime<Local>,
+}
+
+impl PollVote {
+    /// Stores a vote, unless `voter` has already voted in the poll.
+    /// Returns whether the vote was stored.
+    pub async fn cast(
+        db: &DatabaseHandler,
+        poll_id: Uuid,
+        option_index: i32,
+        voter: Uuid,
+    ) -> Result<bool, Error> {
+        Ok(
+            sqlx::query_file!("sql/polls/insert_vote.sql", poll_id, option_index, voter)
+                .execute(&db.pool)
+                .await?
+                .rows_affected()
+                > 0,
+        )
+    }
+
+    pub async fn has_voted(
+        db: &DatabaseHandler,
+        poll_id: Uuid,
+        voter: Uuid,
+    ) -> Result<bool, Error> {
+        Ok(sqlx::query_file!("sql/polls/has_voted.sql", poll_id, voter)
+            .fetch_one(&db.pool)
+            .await?
+            .has_voted)
+    }
+
+    /// Gets the number of votes for each of the first `options` options of a poll.
+    pub async fn count(
+        db: &DatabaseHandler,
+        poll_id: Uuid,
+        options: usize,
+    ) -> Result<Vec<i64>, Error> {
+        let mut votes = vec![0; options];
+        for row in sqlx::query_file!("sql/polls/count_votes.sql", poll_id)
+            .fetch_all(&db.pool)
+            .await?
+        {
+            if let Some(option_votes) = votes.get_mut(row.option_index as usize) {
+                *option_votes = row.votes;
+            }
+        }
+
+        Ok(votes)
+    }
+
+    /// Gets the number of votes for each option of several polls in one go, by poll id.
+    /// The options are indexed as in `PollVote::count`, but only up to the last option anyone has voted for.
+    pub async fn count_by_poll_ids(
+        db: &DatabaseHandler,
+        poll_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<i64>>, Error> {
+        let mut votes: HashMap<Uuid, Vec<i64>> = HashMap::new();
+        for row in sqlx::query_file!("sql/polls/count_votes_by_poll_ids.sql", poll_ids)
+            .fetch_all(&db.pool)
+            .await?
+        {
+            let poll_votes = votes.entry(row.poll_id).or_default();
+            let option_index = row.option_index as usize;
+            if poll_votes.len() <= option_index {
+                poll_votes.resize(option_index + 1, 0);
+            }
+            poll_votes[option_index] = row.votes;
+        }
+
+        Ok(votes)
+    }
+}
+
+/// A `Poll` block in a text or page, with where it is and how many have voted in it.
+/// Polls in snippets are in every text and page where the snippet is used.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StoredPoll {
+    pub poll: Json<Poll>,
+    /// The title of the text or page.
+    pub title: String,
+    /// The address of the text or page.
+    pub url: String,
+    /// Whether readers can see the text or page, and so vote.
+    pub is_published: bool,
+    pub votes: i64,
+}
+
+impl StoredPoll {
+    /// Gets ALL polls in texts and pages that are not in the trash, sorted by the title of the text or page.
+    pub async fn get_all(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/polls/get_all.sql")
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ONE poll by its id, if there is one.
+    /// If the same poll is in several texts or pages, a published one is preferred.
+    pub async fn get_by_id(db: &DatabaseHandler, id: Uuid) -> Result<Option<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/polls/get_by_id.sql", id.to_string())
+            .fetch_optional(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets the number of votes for each option of the poll.
+    pub async fn results(&self, db: &DatabaseHandler) -> Result<Vec<i64>, Error> {
+        match self.poll.uuid() {
+            Some(id) => PollVote::count(db, id, self.poll.option_list().len()).await,
+            None => Ok(vec![0; self.poll.option_list().len()]),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Local, TimeDelta};
+use jsonwebtoken::{Header, Validation};
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::DatabaseHandler,
+    defaults::PREVIEW_LINK_MAX_DAYS,
+    error::Error,
+    token::{PreviewClaims, get_decoding_key, get_encoding_key},
+};
+
+use super::{article::Text, creator::Creator};
+
+/// A shareable link that lets anyone holding it read ONE `Text`, even if it is not published.
+/// The link contains a signed JWT (see `PreviewClaims`) that expires at `expires_at`,
+/// and the link can be revoked before that.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PreviewLink {
+    pub id: i32,
+    pub text_id: i32,
+    /// Username of the creator who created the link.
+    pub created_by: String,
+    pub created_at: DateTime<Local>,
+    pub expires_at: DateTime<Local>,
+    pub revoked_at: Option<DateTime<Local>>,
+    /// Title of the text, used when listing links.
+    pub text_title: String,
+    pub text_title_slug: String,
+}
+
+impl PreviewLink {
+    /// Creates a new preview link for a `Text`.
+    /// * `executor` must be an author of the text or a publisher.
+    /// * `valid_days` how many days the link is valid, at most `PREVIEW_LINK_MAX_DAYS`.
+    pub async fn create(
+        db: &DatabaseHandler,
+        executor: &Creator,
+        text_id: i32,
+        valid_days: i64,
+    ) -> Result<Self, Error> {
+        let text = Text::get_by_id(db, text_id, false).await?;
+
+        if !text.is_author(&executor.username) && !executor.is_publisher() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Must be owner of text or publisher to create a preview link!",
+                Status::Unauthorized,
+            ));
+        }
+
+        if !(1..=PREVIEW_LINK_MAX_DAYS).contains(&valid_days) {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                &format!(
+                    "A preview link must be valid for 1 to {} days!",
+                    PREVIEW_LINK_MAX_DAYS
+                ),
+                Status::BadRequest,
+            ));
+        }
+
+        let expires_at = Local::now() + TimeDelta::days(valid_days);
+
+        sqlx::query_file_as!(
+            Self,
+            "sql/preview_links/insert.sql",
+            text_id,
+            executor.username,
+            expires_at,
+        )
+        .fetch_one(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Gets ONE `PreviewLink` from the database by its id.
+    pub async fn get_by_id(db: &DatabaseHandler, id: i32) -> Result<Self, Error> {
+        sqlx::query_file_as!(Self, "sql/preview_links/get_by_id.sql", id)
+            .fetch_one(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ALL links that are neither expired nor revoked, the ones expiring first first.
+    pub async fn get_active(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/preview_links/get_active.sql")
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Same as `get_active`, but only for ONE `Text`.
+    pub async fn get_active_by_text_id(
+        db: &DatabaseHandler,
+        text_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/preview_links/get_active_by_text_id.sql", text_id)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Revokes a link, so that it can no longer be used.
+    /// * `executor` must be a publisher.
+    pub async fn revoke(db: &DatabaseHandler, executor: &Creator, id: i32) -> Result<Self, Error> {
+        if !executor.is_publisher() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Must be publisher to revoke a preview link!",
+                Status::Unauthorized,
+            ));
+        }
+
+        let link = Self::get_by_id(db, id).await?;
+
+        sqlx::query!(
+            "UPDATE preview_links SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL",
+            id
+        )
+        .execute(&db.pool)
+        .await?;
+
+        Ok(link)
+    }
+
+    /// Gets the signed token of the link, to be used in `/preview/<token>`.
+    /// The token only contains the ids and the expiry, so the same link always gives the same token.
+    pub fn token(&self) -> Result<String, Error> {
+        let claims = PreviewClaims {
+            exp: self.expires_at.timestamp() as usize,
+            sub: self.text_id,
+            jti: self.id,
+        };
+
+        jsonwebtoken::encode::<PreviewClaims>(&Header::default(), &claims, &get_encoding_key())
+            .map_err(|_| {
+                Error::create(
+                    "Token creation",
+                    "Failed to encode token!",
+                    Status::InternalServerError,
+                )
+            })
+    }
+
+    /// Checks a token from a preview link and gets the `Text` it grants access to.
+    /// Fails if the token is invalid, expired or revoked.
+    pub async fn get_text_by_token(db: &DatabaseHandler, token: &str) -> Result<Text, Error> {
+        let invalid = || {
+            Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Invalid, expired or revoked preview link!",
+                Status::NotFound,
+            )
+        };
+
+        let claims = jsonwebtoken::decode::<PreviewClaims>(
+            token,
+            &get_decoding_key(),
+            &Validation::default(),
+        )
+        .map_err(|_| invalid())?
+        .claims;
+
+        let link = Self::get_by_id(db, claims.jti)
+            .await
+            .map_err(|_| invalid())?;
+
+        if link.text_id != claims.sub || link.revoked_at.is_some() || link.expires_at < Local::now()
+        {
+            return Err(invalid());
+        }
+
+        Text::get_by_id(db, link.text_id, false).await
+    }
+}
@@ -0,0 +1,159 @@
+use chrono::{DateTime, Local};
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, error::Error};
+
+use super::{article::Text, creator::Creator};
+
+/// A `ReviewComment` is internal feedback on a `Text`, e.g. from a publisher reviewing it.
+/// These are NEVER shown to readers.
+///
+/// Comments form threads: a comment without a `parent_id` starts a thread,
+/// and replies reference it. Only the first comment in a thread is resolved.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReviewComment {
+    pub id: i32,
+    pub text_id: i32,
+    /// The comment this is a reply to.
+    pub parent_id: Option<i32>,
+    /// Index of the block in `Text::text_body` the comment is about; `None` for the whole text.
+    pub block_index: Option<i32>,
+    /// Username of the creator who wrote the comment.
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Local>,
+    pub resolved_at: Option<DateTime<Local>>,
+    /// Username of the creator who resolved the thread.
+    pub resolved_by: Option<String>,
+}
+
+impl ReviewComment {
+    /// Checks that `executor` may read and write review comments on `text`.
+    fn check_access(text: &Text, executor: &Creator) -> Result<(), Error> {
+        if !text.is_author(&executor.username) && !executor.is_publisher() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "Must be owner of text or publisher to handle review comments!",
+                Status::Unauthorized,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Adds a new comment to a `Text`.
+    /// * `parent_id` the comment to reply to, `None` to start a new thread.
+    /// * `block_index` the block the comment is about, `None` for the whole text.
+    pub async fn add(
+        db: &DatabaseHandler,
+        executor: &Creator,
+        text_id: i32,
+        parent_id: Option<i32>,
+        block_index: Option<i32>,
+        body: &str,
+    ) -> Result<Self, Error> {
+        let text = Text::get_by_id(db, text_id, false).await?;
+        Self::check_access(&text, executor)?;
+
+        if body.trim().is_empty() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "A comment cannot be empty!",
+                Status::BadRequest,
+            ));
+        }
+
+        if let Some(block_index) = block_index
+            && (block_index < 0 || block_index as usize >= text.text_body.len())
+        {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                "The comment refers to a block that does not exist!",
+                Status::BadRequest,
+            ));
+        }
+
+        // Replies always belong to the thread of the comment they reply to.
+        let (parent_id, block_index) = match parent_id {
+            Some(parent_id) => {
+                let parent = Self::get_by_id(db, parent_id).await?;
+                if parent.text_id != text_id {
+                    return Err(Error::create(
+                        &format!("{}:{}", file!(), line!()),
+                        "Cannot reply to a comment on another text!",
+                        Status::BadRequest,
+                    ));
+                }
+                (
+                    Some(parent.parent_id.unwrap_or(parent.id)),
+                    parent.block_index,
+                )
+            }
+            None => (None, block_index),
+        };
+
+        sqlx::query_file_as!(
+            Self,
+            "sql/review_comments/insert.sql",
+            text_id,
+            parent_id,
+            block_index,
+            executor.username,
+            body,
+        )
+        .fetch_one(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Gets ONE `ReviewComment` from the database by its id.
+    pub async fn get_by_id(db: &DatabaseHandler, id: i32) -> Result<Self, Error> {
+        sqlx::query_file_as!(Self, "sql/review_comments/get_by_id.sql", id)
+            .fetch_one(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ALL comments on a `Text`, oldest first.
+    pub async fn get_by_text_id(db: &DatabaseHandler, text_id: i32) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/review_comments/get_by_text_id.sql", text_id)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets the count of unresolved threads on a `Text`.
+    pub async fn get_unresolved_count(db: &DatabaseHandler, text_id: i32) -> Result<i64, Error> {
+        sqlx::query_file_scalar!("sql/review_comments/get_unresolved_count.sql", text_id)
+            .fetch_one(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Marks the thread a comment belongs to as resolved, or reopens it.
+    /// * `resolved` `true` to resolve, `false` to reopen.
+    pub async fn set_resolved(
+        db: &DatabaseHandler,
+        executor: &Creator,
+        id: i32,
+        resolved: bool,
+    ) -> Result<Self, Error> {
+        let comment = Self::get_by_id(db, id).await?;
+        let text = Text::get_by_id(db, comment.text_id, false).await?;
+        Self::check_access(&text, executor)?;
+
+        let thread_id = comment.parent_id.unwrap_or(comment.id);
+
+        sqlx::query!(
+            "UPDATE review_comments SET resolved_at = CASE WHEN $1 THEN NOW() END, resolved_by = CASE WHEN $1 THEN $2 END WHERE id = $3",
+            resolved,
+            executor.username,
+            thread_id,
+        )
+        .execute(&db.pool)
+        .await?;
+
+        Ok(comment)
+    }
+}
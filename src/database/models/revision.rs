@@ -0,0 +1,175 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, types::Json};
+use uuid::Uuid;
+
+use crate::{block_editor::Block, database::DatabaseHandler, error::Error};
+
+use super::article::{Text, TextType};
+
+/// A `TextRevision` is a snapshot of a `Text`, stored every time the text is saved.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TextRevision {
+    pub id: i32,
+    /// Reference to the `Text` this is a revision of.
+    pub text_id: i32,
+    /// Username of the creator who saved this revision.
+    pub author: String,
+    pub created_at: DateTime<Local>,
+    pub title: String,
+    pub thumbnail_id: Option<Uuid>,
+    pub lead_paragraph: String,
+    pub text_body: Json<Vec<Block>>,
+    pub text_type: TextType,
+    pub tags: Vec<String>,
+}
+
+/// How a block differs between two revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DiffKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+/// One entry in a block-by-block diff between two revisions.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlockDiff {
+    pub kind: DiffKind,
+    pub block: Block,
+}
+
+impl TextRevision {
+    /// Stores the current state of `text` as a new revision.
+    /// * `executor` is the database pool, or the transaction that saves the text.
+    /// * `author` is the username of the creator who saved the text.
+    pub async fn save_from_text(
+        executor: impl PgExecutor<'_>,
+        text: &Text,
+        author: &str,
+    ) -> Result<Self, Error> {
+        sqlx::query_file_as!(
+            Self,
+            "sql/text_revisions/insert.sql",
+            text.id,
+            author,
+            text.title,
+            text.thumbnail_id,
+            text.lead_paragraph,
+            serde_json::to_value(text.text_body.clone())?,
+            &text.text_type as &TextType,
+            &text.tags,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Gets ONE `TextRevision` from the database by its id.
+    pub async fn get_by_id(db: &DatabaseHandler, id: i32) -> Result<Self, Error> {
+        sqlx::query_file_as!(Self, "sql/text_revisions/get_by_id.sql", id)
+            .fetch_one(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ALL revisions of a `Text`, newest first.
+    pub async fn get_by_text_id(db: &DatabaseHandler, text_id: i32) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/text_revisions/get_by_text_id.sql", text_id)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Creates a block-by-block diff going from `old` to `new`.
+    /// Blocks are matched using the longest common subsequence, so moved or
+    /// edited blocks show up as one removed and one added block.
+    pub fn diff_blocks(old: &[Block], new: &[Block]) -> Vec<BlockDiff> {
+        // `lcs[i][j]` is the length of the longest common subsequence of `old[i..]` and `new[j..]`.
+        let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = match old[i] == new[j] {
+                    true => lcs[i + 1][j + 1] + 1,
+                    false => lcs[i + 1][j].max(lcs[i][j + 1]),
+                };
+            }
+        }
+
+        let mut diff = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() && j < new.len() {
+            if old[i] == new[j] {
+                diff.push(BlockDiff {
+                    kind: DiffKind::Unchanged,
+                    block: new[j].clone(),
+                });
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                diff.push(BlockDiff {
+                    kind: DiffKind::Removed,
+                    block: old[i].clone(),
+                });
+                i += 1;
+            } else {
+                diff.push(BlockDiff {
+                    kind: DiffKind::Added,
+                    block: new[j].clone(),
+                });
+                j += 1;
+            }
+        }
+
+        diff.extend(old[i..].iter().map(|block| BlockDiff {
+            kind: DiffKind::Removed,
+            block: block.clone(),
+        }));
+        diff.extend(new[j..].iter().map(|block| BlockDiff {
+            kind: DiffKind::Added,
+            block: block.clone(),
+        }));
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_editor::paragraph::Paragraph;
+
+    fn paragraph(text: &str) -> Block {
+        Block::new(Paragraph {
+            body_text: text.to_string(),
+        })
+    }
+
+    /// Tests that unchanged, removed and added blocks are found in the correct order.
+    #[test]
+    fn test_diff_blocks() {
+        let old = [paragraph("a"), paragraph("b"), paragraph("c")];
+        let new = [
+            paragraph("a"),
+            paragraph("x"),
+            paragraph("c"),
+            paragraph("d"),
+        ];
+
+        let kinds = TextRevision::diff_blocks(&old, &new)
+            .into_iter()
+            .map(|diff| diff.kind)
+            .collect::<Vec<DiffKind>>();
+
+        assert_eq!(
+            kinds,
+            [
+                DiffKind::Unchanged,
+                DiffKind::Removed,
+                DiffKind::Added,
+                DiffKind::Unchanged,
+                DiffKind::Added,
+            ]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Local};
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{postgres::PgQueryResult, types::Json};
+use uuid::Uuid;
+
+use crate::{block_editor::Block, database::DatabaseHandler, error::Error};
+
+/// Blocks that are edited in one place and shown wherever a `Snippet` block refers to them,
+/// e.g. a newsletter signup or a fundraising appeal.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Snippet {
+    pub id: Uuid,
+    /// Only shown to creators, to tell the snippets apart.
+    pub name: String,
+    pub text_body: Json<Vec<Block>>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+/// A text or page that refers to a `Snippet`, see `Snippet::usage`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnippetUsage {
+    /// `text` or `page`.
+    pub kind: String,
+    /// The id of the text or the path of the page.
+    pub key: String,
+    pub title: String,
+}
+
+impl Snippet {
+    /// Saves a new `Snippet` to the database.
+    pub async fn insert(
+        db: &DatabaseHandler,
+        name: &str,
+        text_body: Vec<Block>,
+    ) -> Result<Self, Error> {
+        sqlx::query_file_as!(
+            Self,
+            "sql/snippets/insert.sql",
+            Uuid::new_v4(),
+            name,
+            serde_json::to_value(text_body)?,
+        )
+        .fetch_one(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Updates ONE `Snippet` by its `id`.
+    /// * `loaded_updated_at` the `updated_at` of the snippet when the editor loaded it.
+    ///   If the snippet has been updated since, nothing is saved and a `409 Conflict` error is returned.
+    ///   `None` skips this check.
+    pub async fn update(
+        db: &DatabaseHandler,
+        id: Uuid,
+        name: &str,
+        text_body: Vec<Block>,
+        loaded_updated_at: Option<DateTime<Local>>,
+    ) -> Result<Self, Error> {
+        sqlx::query_file_as!(
+            Self,
+            "sql/snippets/update.sql",
+            id,
+            name,
+            serde_json::to_value(text_body)?,
+            loaded_updated_at,
+        )
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or(Error::create(
+            &format!("{}:{}", file!(), line!()),
+            "The snippet has been changed by someone else since it was loaded!",
+            Status::Conflict,
+        ))
+    }
+
+    /// Gets ONE `Snippet` from the database by its id.
+    pub async fn get_by_id(db: &DatabaseHandler, id: Uuid) -> Result<Self, Error> {
+        sqlx::query_file_as!(Self, "sql/snippets/get_by_id.sql", id)
+            .fetch_one(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets the `Snippet`s with any of `ids` in one go; ids of missing snippets are left out.
+    pub async fn get_by_ids(db: &DatabaseHandler, ids: &[Uuid]) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/snippets/get_by_ids.sql", ids)
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets ALL `Snippet`s from the database, by name.
+    pub async fn get_all(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(Self, "sql/snippets/get_all.sql")
+            .fetch_all(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Gets the texts and pages, not in the trash, that have a `Snippet` block referring to the snippet.
+    pub async fn usage(db: &DatabaseHandler, id: Uuid) -> Result<Vec<SnippetUsage>, Error> {
+        sqlx::query_file_as!(
+            SnippetUsage,
+            "sql/snippets/usage.sql",
+            json!([{ "type": "Snippet", "id": id.to_string() }]),
+        )
+        .fetch_all(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Deletes ONE `Snippet` by its id; this cannot be undone.
+    pub async fn delete(db: &DatabaseHandler, id: Uuid) -> Result<PgQueryResult, Error> {
+        sqlx::query!("DELETE FROM snippets WHERE id = $1", id)
+            .execute(&db.pool)
+            .await
+            .map_err(Error::from)
+    }
+}
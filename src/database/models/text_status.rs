@@ -0,0 +1,209 @@
+use chrono::{DateTime, Local};
+use rocket::{http::Status, request::FromParam};
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, error::Error};
+
+use super::creator::Creator;
+
+/// The editorial state of a `Text`.
+///
+/// A text starts as a `Draft`, is sent to review by its author and is then
+/// either approved or sent back with changes requested by a publisher.
+/// Only approved (or previously unpublished) texts can be published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[sqlx(type_name = "text_status", rename_all = "snake_case")]
+pub enum TextStatus {
+    Draft,
+    InReview,
+    ChangesRequested,
+    Approved,
+    Published,
+    Unpublished,
+    Archived,
+}
+
+/// Who is allowed to perform a transition between two states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransitionActor {
+    /// The author of the text, or any publisher.
+    Author,
+    /// Only publishers.
+    Publisher,
+}
+
+impl<'a> FromParam<'a> for TextStatus {
+    type Error = crate::error::Error;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        match param {
+            "Draft" | "draft" => Ok(TextStatus::Draft),
+            "InReview" | "in_review" => Ok(TextStatus::InReview),
+            "ChangesRequested" | "changes_requested" => Ok(TextStatus::ChangesRequested),
+            "Approved" | "approved" => Ok(TextStatus::Approved),
+            "Published" | "published" => Ok(TextStatus::Published),
+            "Unpublished" | "unpublished" => Ok(TextStatus::Unpublished),
+            "Archived" | "archived" => Ok(TextStatus::Archived),
+            _ => Err(Self::Error::create(
+                "FromParam for TextStatus",
+                &format!("{} is not a valid TextStatus", param),
+                Status::BadRequest,
+            )),
+        }
+    }
+}
+
+impl TextStatus {
+    /// All states, in workflow order.
+    pub const ALL: [TextStatus; 7] = [
+        TextStatus::Draft,
+        TextStatus::InReview,
+        TextStatus::ChangesRequested,
+        TextStatus::Approved,
+        TextStatus::Published,
+        TextStatus::Unpublished,
+        TextStatus::Archived,
+    ];
+
+    /// The rules of the workflow; this is the ONLY place where transitions are defined.
+    /// Returns who may perform the transition, or `None` if the transition is not allowed.
+    fn transition_actor(self, to: TextStatus) -> Option<TransitionActor> {
+        use TextStatus::*;
+        use TransitionActor::*;
+
+        match (self, to) {
+            (Draft, InReview) => Some(Author),
+            (InReview, Draft) => Some(Author),
+            (InReview, ChangesRequested) => Some(Publisher),
+            (InReview, Approved) => Some(Publisher),
+            (ChangesRequested, InReview) => Some(Author),
+            (ChangesRequested, Draft) => Some(Author),
+            (Approved, Published) => Some(Publisher),
+            (Approved, ChangesRequested) => Some(Publisher),
+            (Published, Unpublished) => Some(Publisher),
+            (Unpublished, Published) => Some(Publisher),
+            (Unpublished, Draft) => Some(Author),
+            (Archived, Draft) => Some(Publisher),
+            (from, Archived) if from != Archived => Some(Publisher),
+            _ => None,
+        }
+    }
+
+    /// Checks if `executor` may move a text from this state to `to`.
+    /// * `is_author` should be `true` if `executor` is an author of the text.
+    pub fn can_transition(self, to: TextStatus, executor: &Creator, is_author: bool) -> bool {
+        match self.transition_actor(to) {
+            Some(TransitionActor::Author) => is_author || executor.is_publisher(),
+            Some(TransitionActor::Publisher) => executor.is_publisher(),
+            None => false,
+        }
+    }
+
+    /// Same as `can_transition`, but returns a descriptive error if not allowed.
+    pub fn check_transition(
+        self,
+        to: TextStatus,
+        executor: &Creator,
+        is_author: bool,
+    ) -> Result<(), Error> {
+        if self.transition_actor(to).is_none() {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                &format!("Cannot change status from {:?} to {:?}!", self, to),
+                Status::BadRequest,
+            ));
+        }
+
+        if !self.can_transition(to, executor, is_author) {
+            return Err(Error::create(
+                &format!("{}:{}", file!(), line!()),
+                &format!(
+                    "You are not allowed to change status from {:?} to {:?}!",
+                    self, to
+                ),
+                Status::Unauthorized,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Gets all states `executor` may move a text in this state to.
+    pub fn available_transitions(self, executor: &Creator, is_author: bool) -> Vec<TextStatus> {
+        Self::ALL
+            .into_iter()
+            .filter(|to| self.can_transition(*to, executor, is_author))
+            .collect()
+    }
+
+    /// Checks if the author of a text (who is not a publisher) may edit it in this state.
+    pub fn is_editable_by_author(self) -> bool {
+        matches!(
+            self,
+            TextStatus::Draft
+                | TextStatus::InReview
+                | TextStatus::ChangesRequested
+                | TextStatus::Unpublished
+        )
+    }
+}
+
+/// A record of a `Text` changing state.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StatusTransition {
+    pub id: i32,
+    pub text_id: i32,
+    pub from_status: TextStatus,
+    pub to_status: TextStatus,
+    /// Username of the creator who changed the state; `None` if it was done automatically.
+    pub actor: Option<String>,
+    pub created_at: DateTime<Local>,
+}
+
+impl StatusTransition {
+    /// Gets the transition history of a `Text`, newest first.
+    pub async fn get_by_text_id(db: &DatabaseHandler, text_id: i32) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(
+            Self,
+            "sql/text_status_transitions/get_by_text_id.sql",
+            text_id
+        )
+        .fetch_all(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::creator::CreatorRole;
+
+    /// Tests that writers can only move their own texts through the writer part of the workflow.
+    #[test]
+    fn test_transition_rules() {
+        let writer = Creator::default();
+        let publisher = Creator {
+            role: CreatorRole::Publisher,
+            ..Default::default()
+        };
+
+        assert!(TextStatus::Draft.can_transition(TextStatus::InReview, &writer, true));
+        assert!(!TextStatus::Draft.can_transition(TextStatus::InReview, &writer, false));
+        assert!(!TextStatus::InReview.can_transition(TextStatus::Approved, &writer, true));
+        assert!(TextStatus::InReview.can_transition(TextStatus::Approved, &publisher, false));
+        assert!(!TextStatus::Draft.can_transition(TextStatus::Published, &publisher, true));
+        assert!(TextStatus::Approved.can_transition(TextStatus::Published, &publisher, false));
+        assert!(!TextStatus::Archived.can_transition(TextStatus::Archived, &publisher, false));
+
+        assert_eq!(
+            TextStatus::Published.available_transitions(&publisher, false),
+            [TextStatus::Unpublished, TextStatus::Archived]
+        );
+        assert!(
+            TextStatus::Published
+                .available_transitions(&writer, true)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::env;
+
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::{database::DatabaseHandler, defaults::TRASH_RETENTION_DAYS, error::Error};
+
+use super::{article::Text, audio::Audio, image::Image, page::Page};
+
+/// What kind of item is in the trash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+pub enum TrashKind {
+    Text,
+    Page,
+    Image,
+    Audio,
+}
+
+/// Something in the trash: a trashed `Text`, `Page`, `Image` or `Audio`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrashedItem {
+    pub kind: TrashKind,
+    /// The id of a text, image or audio, or the path of a page.
+    pub id: String,
+    /// The title of a text, page or audio, or the description of an image.
+    pub title: String,
+    pub deleted_at: DateTime<Local>,
+    /// When the item will be permanently deleted.
+    pub purge_at: DateTime<Local>,
+}
+
+impl TrashedItem {
+    /// Gets for how many days trashed items are kept.
+    /// Defaults to `TRASH_RETENTION_DAYS`, but can be set with the `TRASH_RETENTION_DAYS` environment variable.
+    /// Values that are not a whole number of days from 0 to 65535 are ignored.
+    pub fn retention_days() -> u16 {
+        env::var("TRASH_RETENTION_DAYS")
+            .ok()
+            .and_then(|days| days.trim().parse().ok())
+            .unwrap_or(TRASH_RETENTION_DAYS)
+    }
+
+    /// Gets ALL trashed items, most recently trashed first.
+    pub async fn get_all(db: &DatabaseHandler) -> Result<Vec<Self>, Error> {
+        sqlx::query_file_as!(
+            Self,
+            "sql/trash/get_all.sql",
+            i32::from(Self::retention_days())
+        )
+        .fetch_all(&db.pool)
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Permanently deletes ALL items that have been in the trash longer than the retention period.
+    /// Returns the number of deleted items.
+    pub async fn purge_expired(db: &DatabaseHandler) -> Result<u64, Error> {
+        let before = Local::now() - TimeDelta::days(i64::from(Self::retention_days()));
+
+        Ok(Text::purge_trashed(db, before).await?
+            + Page::purge_trashed(db, before).await?
+            + Image::purge_trashed(db, before).await?
+            + Audio::purge_trashed(db, before).await?)
+    }
+}
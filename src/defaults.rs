@@ -1 +1,97 @@
 pub const DATA_DIR: &str = "./data";
+/// How often (in seconds) the scheduler checks for texts that are due to be published.
+pub const SCHEDULER_INTERVAL_SECS: u64 = 30;
+/// For how long (in seconds) someone is shown as having a text open after the editor last pinged.
+pub const PRESENCE_TIMEOUT_SECS: u64 = 60;
+/// For how many days trashed texts, pages, images and audio files are kept before they are purged,
+/// unless overridden by the `TRASH_RETENTION_DAYS` environment variable.
+pub const TRASH_RETENTION_DAYS: u16 = 30;
+/// For how many days a preview link may be valid at most.
+pub const PREVIEW_LINK_MAX_DAYS: i64 = 30;
+/// The tags that are kept in `RawHtml` blocks,
+/// unless overridden by the comma separated `RAW_HTML_ALLOWED_TAGS` environment variable.
+pub const RAW_HTML_ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "code",
+    "div",
+    "em",
+    "figcaption",
+    "figure",
+    "h2",
+    "h3",
+    "h4",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+    "audio",
+    "video",
+];
+/// The attributes, besides the ones that are always allowed, that are kept on tags in `RawHtml` blocks.
+/// Iframes load content from other sites without the reader's consent, so they are only kept if
+/// `RAW_HTML_ALLOWED_TAGS` is set to include `iframe`.
+pub const RAW_HTML_TAG_ATTRIBUTES: &[(&str, &[&str])] = &[
+    (
+        "iframe",
+        &[
+            "src",
+            "width",
+            "height",
+            "title",
+            "allow",
+            "allowfullscreen",
+            "loading",
+        ],
+    ),
+    ("audio", &["src", "controls"]),
+    ("video", &["src", "controls", "width", "height", "poster"]),
+    ("source", &["src", "type"]),
+];
+/// The audio formats that can be uploaded: the MIME types browsers send, the MIME type that is stored, and the file extension.
+pub const AUDIO_FORMATS: &[(&str, &str, &str)] = &[
+    ("audio/mpeg", "audio/mpeg", "mp3"),
+    ("audio/mp3", "audio/mpeg", "mp3"),
+    ("audio/mp4", "audio/mp4", "m4a"),
+    ("audio/x-m4a", "audio/mp4", "m4a"),
+    ("audio/ogg", "audio/ogg", "ogg"),
+    ("audio/wav", "audio/wav", "wav"),
+    ("audio/x-wav", "audio/wav", "wav"),
+];
+/// How many texts with audio the podcast feed has at most.
+pub const PODCAST_FEED_MAX_EPISODES: i64 = 100;
+/// How many characters short block fields, e.g. headings and captions, may be at most.
+pub const BLOCK_SHORT_FIELD_MAX_CHARS: usize = 300;
+/// How many characters long block fields, e.g. paragraphs, may be at most.
+pub const BLOCK_LONG_FIELD_MAX_CHARS: usize = 50_000;
+/// How many options a poll may have at most.
+pub const POLL_MAX_OPTIONS: usize = 20;
+/// How many votes in polls one IP address may cast within `POLL_VOTE_WINDOW_SECS`.
+pub const POLL_VOTES_PER_WINDOW: usize = 10;
+/// The window (in seconds) of `POLL_VOTES_PER_WINDOW`.
+pub const POLL_VOTE_WINDOW_SECS: u64 = 600;
+/// For how many days the cookie that identifies a voter in polls is kept.
+pub const POLL_VOTER_COOKIE_DAYS: i64 = 365;
+/// How many headings a text or page must have to get a table of contents.
+pub const TABLE_OF_CONTENTS_MIN_HEADINGS: usize = 3;
@@ -21,11 +21,18 @@ pub mod database;
 pub mod defaults;
 pub mod error;
 pub mod flash_msg;
+pub mod presence;
+pub mod ranged_file;
+pub mod rate_limit;
+pub mod scheduler;
 pub mod token;
 
 use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
-use comrak::{Options, markdown_to_html};
+use block_editor::{
+    render::{RenderTarget, render_body},
+    table_of_contents::table_of_contents,
+};
 use database::{
     DatabaseHandler,
     models::{image::Image, page::Page},
@@ -48,9 +55,7 @@ fn custom_tera(engines: &mut Engines) {
         "markdown",
         |value: &tera::Value, _: &_| -> tera::Result<tera::Value> {
             let markdown = tera::from_value::<String>(value.clone())?;
-            let raw_html = markdown_to_html(&markdown, &Options::default());
-            let sanitized_html = ammonia::clean(&raw_html);
-            Ok(tera::to_value(sanitized_html)?)
+            Ok(tera::to_value(block_editor::html::markdown(&markdown))?)
         },
     );
 
@@ -58,8 +63,7 @@ fn custom_tera(engines: &mut Engines) {
         "sanitize",
         |value: &tera::Value, _: &_| -> tera::Result<tera::Value> {
             let html = tera::from_value::<String>(value.clone())?;
-            let sanitized_html = ammonia::clean(&html);
-            Ok(tera::to_value(sanitized_html)?)
+            Ok(tera::to_value(block_editor::html::sanitize(&html))?)
         },
     );
 
@@ -87,6 +91,13 @@ fn custom_tera(engines: &mut Engines) {
         },
     );
 
+    engines.tera.register_function(
+        "block_kinds",
+        |_: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+            Ok(tera::to_value(block_editor::registry().schemas())?)
+        },
+    );
+
     engines.tera.register_function(
         "image",
         |value: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
@@ -153,15 +164,10 @@ async fn page_finder(
         Err(_) => return None,
     };
 
-    let mut rendered_blocks: Vec<String> = Vec::new();
-    for block in page.text_body.iter() {
-        rendered_blocks.push(
-            block
-                .render(db)
-                .await
-                .unwrap_or("INVALID BLOCK!".to_string()),
-        );
-    }
+    let rendered_blocks = render_body(db, &page.text_body, RenderTarget::Site)
+        .await
+        .ok()?;
+    let table_of_contents = table_of_contents(&page.text_body);
 
     let is_admin = match claims {
         Some(claims) => claims.data.is_publisher(),
@@ -170,12 +176,15 @@ async fn page_finder(
 
     Some(Template::render(
         "single-page-view",
-        context! { rendered_blocks: rendered_blocks.join(""), page, is_admin },
+        context! { rendered_blocks, table_of_contents, page, is_admin },
     ))
 }
 
 #[rocket::main]
 async fn main() {
+    // Register the block types before anything is loaded from the database.
+    block_editor::install_registry(block_editor::BlockRegistry::with_builtin_kinds());
+
     // Initialize the database connection.
     let database = match DatabaseHandler::create().await {
         Ok(db) => db,
@@ -185,11 +194,24 @@ async fn main() {
         ),
     };
 
+    // Start the scheduler with its own connection to the database.
+    match DatabaseHandler::create().await {
+        Ok(scheduler_db) => {
+            tokio::spawn(scheduler::run(scheduler_db));
+        }
+        Err(err) => panic!(
+            "Encountered an error while connecting to database for the scheduler!\n{:?}",
+            err
+        ),
+    };
+
     // Launch the application
     match rocket::build()
         //.attach(Template::fairing())
         .attach(Template::custom(custom_tera))
         .manage(database)
+        .manage(presence::Presence::default())
+        .manage(rate_limit::RateLimit::for_poll_votes())
         .mount("/", app::get_all_routes())
         .mount("/api", api::get_all_routes())
         .mount("/texts", app::texts::get_all_routes())
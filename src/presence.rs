@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::defaults::PRESENCE_TIMEOUT_SECS;
+
+/// Who has a text open: username -> (display name, last ping).
+type TextEditors = HashMap<String, (String, Instant)>;
+
+/// Keeps track of who has which text open in the editor.
+/// The editor pings regularly, and anyone who has not pinged within `PRESENCE_TIMEOUT_SECS` is forgotten.
+///
+/// This is only kept in memory, so it is emptied when the server restarts.
+#[derive(Debug, Default)]
+pub struct Presence {
+    /// Text id -> editors of that text.
+    editors: Mutex<HashMap<i32, TextEditors>>,
+}
+
+impl Presence {
+    /// Marks that `username` has the text open right now.
+    /// Everyone who has timed out, for any text, is forgotten, so that texts that nobody pings any more do not linger.
+    pub fn touch(&self, text_id: i32, username: &str, display_name: &str) {
+        let timeout = Duration::from_secs(PRESENCE_TIMEOUT_SECS);
+        let mut editors = self.editors.lock().unwrap_or_else(|err| err.into_inner());
+        editors.retain(|_, text_editors| {
+            text_editors.retain(|_, (_, last_ping)| last_ping.elapsed() < timeout);
+            !text_editors.is_empty()
+        });
+        editors
+            .entry(text_id)
+            .or_default()
+            .insert(username.into(), (display_name.into(), Instant::now()));
+    }
+
+    /// Marks that `username` has closed the text.
+    pub fn leave(&self, text_id: i32, username: &str) {
+        let mut editors = self.editors.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(text_editors) = editors.get_mut(&text_id) {
+            text_editors.remove(username);
+            if text_editors.is_empty() {
+                editors.remove(&text_id);
+            }
+        }
+    }
+
+    /// Gets the display names of everyone but `username` who has the text open, sorted by name.
+    pub fn others(&self, text_id: i32, username: &str) -> Vec<String> {
+        let timeout = Duration::from_secs(PRESENCE_TIMEOUT_SECS);
+        let mut editors = self.editors.lock().unwrap_or_else(|err| err.into_inner());
+
+        let Some(text_editors) = editors.get_mut(&text_id) else {
+            return Vec::new();
+        };
+        text_editors.retain(|_, (_, last_ping)| last_ping.elapsed() < timeout);
+
+        let mut others = text_editors
+            .iter()
+            .filter(|(other, _)| *other != username)
+            .map(|(_, (display_name, _))| display_name.clone())
+            .collect::<Vec<String>>();
+        others.sort();
+
+        others
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that creators only see the others who have a text open.
+    #[test]
+    fn test_presence() {
+        let presence = Presence::default();
+
+        presence.touch(1, "anna", "Anna");
+        presence.touch(1, "bo", "Bo");
+        presence.touch(2, "cecilia", "Cecilia");
+
+        assert_eq!(presence.others(1, "anna"), ["Bo"]);
+        assert_eq!(presence.others(1, "cecilia"), ["Anna", "Bo"]);
+
+        presence.leave(1, "bo");
+        assert!(presence.others(1, "anna").is_empty());
+        assert!(presence.others(3, "anna").is_empty());
+    }
+
+    /// Tests that texts whose editors have all timed out are forgotten when anyone pings.
+    #[test]
+    fn test_prune() {
+        let presence = Presence::default();
+        let timed_out = Instant::now() - Duration::from_secs(PRESENCE_TIMEOUT_SECS + 1);
+        presence.editors.lock().unwrap().insert(
+            2,
+            HashMap::from([("bo".to_string(), ("Bo".to_string(), timed_out))]),
+        );
+
+        presence.touch(1, "anna", "Anna");
+
+        let editors = presence.editors.lock().unwrap();
+        assert!(editors.contains_key(&1));
+        assert!(!editors.contains_key(&2));
+    }
+}
@@ -0,0 +1,216 @@
+//! Serves files with support for HTTP range requests, which `FileServer` lacks.
+//! Audio players need them to seek, and podcast apps to resume downloads.
+
+use std::{
+    io::{self, SeekFrom},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use rocket::{
+    Request, Response,
+    http::{ContentType, Header, Status},
+    response::{self, Responder},
+    tokio::{
+        fs::File,
+        io::{AsyncRead, AsyncSeek, ReadBuf},
+    },
+};
+
+/// A file that is sent whole, or only the part asked for with a `Range: bytes=...` header.
+pub struct RangedFile {
+    file: File,
+    len: u64,
+    content_type: ContentType,
+}
+
+impl RangedFile {
+    /// Opens the file at `path`, or returns `None` if it cannot be opened.
+    pub async fn open(path: impl AsRef<Path>, content_type: ContentType) -> Option<Self> {
+        let file = File::open(path).await.ok()?;
+        let len = file.metadata().await.ok()?.len();
+        Some(Self {
+            file,
+            len,
+            content_type,
+        })
+    }
+}
+
+/// Parses a `Range` header into the first and last byte it asks for, or `Err` if it cannot be satisfied.
+/// Returns `Ok(None)` for ranges that are not understood, e.g. several ranges, in which case the whole file is sent.
+fn parse_range(range: &str, len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(range) = range.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    let Some((start, end)) = range.split_once('-').filter(|_| !range.contains(',')) else {
+        return Ok(None);
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // The last `suffix` bytes, e.g. `bytes=-500`
+        ("", suffix) => {
+            let suffix = suffix.parse::<u64>().map_err(|_| ())?;
+            if suffix == 0 {
+                return Err(());
+            }
+            (len.saturating_sub(suffix), len.saturating_sub(1))
+        }
+        (start, "") => (start.parse::<u64>().map_err(|_| ())?, len.saturating_sub(1)),
+        (start, end) => (
+            start.parse::<u64>().map_err(|_| ())?,
+            end.parse::<u64>()
+                .map_err(|_| ())?
+                .min(len.saturating_sub(1)),
+        ),
+    };
+
+    if start > end || start >= len {
+        return Err(());
+    }
+    Ok(Some((start, end)))
+}
+
+/// A part of a file, from `start` up to but not including `end`.
+/// Reading starts at `start` and stops at `end`, and seeking is relative to `start`.
+struct FileRange {
+    file: File,
+    start: u64,
+    end: u64,
+    position: u64,
+    seek: SeekState,
+}
+
+/// Whether the file has been moved to `FileRange::start`, which happens on the first read.
+#[derive(PartialEq)]
+enum SeekState {
+    Pending,
+    Started,
+    Done,
+}
+
+impl AsyncRead for FileRange {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.seek == SeekState::Pending {
+            let start = self.start;
+            Pin::new(&mut self.file).start_seek(SeekFrom::Start(start))?;
+            self.seek = SeekState::Started;
+        }
+        if self.seek == SeekState::Started {
+            ready!(Pin::new(&mut self.file).poll_complete(cx))?;
+            self.seek = SeekState::Done;
+        }
+
+        let remaining = self.end.saturating_sub(self.position);
+        if remaining == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        if buf.remaining() as u64 <= remaining {
+            let filled = buf.filled().len();
+            ready!(Pin::new(&mut self.file).poll_read(cx, buf))?;
+            self.position += (buf.filled().len() - filled) as u64;
+        } else {
+            // Only the end of the range is left, so it is read into a buffer of its own to not read past it.
+            let mut chunk = vec![0; remaining as usize];
+            let mut chunk = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut self.file).poll_read(cx, &mut chunk))?;
+            buf.put_slice(chunk.filled());
+            self.position += chunk.filled().len() as u64;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for FileRange {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.seek = SeekState::Done;
+        let position = match position {
+            SeekFrom::Start(offset) => SeekFrom::Start(self.start + offset),
+            SeekFrom::End(offset) => SeekFrom::Start((self.end as i64 + offset).max(0) as u64),
+            SeekFrom::Current(offset) => SeekFrom::Current(offset),
+        };
+        Pin::new(&mut self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        match Pin::new(&mut self.file).poll_complete(cx) {
+            Poll::Ready(Ok(position)) => {
+                self.position = position;
+                Poll::Ready(Ok(position.saturating_sub(self.start)))
+            }
+            other => other,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for RangedFile {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let range = match request.headers().get_one("Range") {
+            Some(range) => parse_range(range, self.len),
+            None => Ok(None),
+        };
+
+        let mut response = Response::build();
+        response
+            .header(self.content_type)
+            .header(Header::new("Accept-Ranges", "bytes"));
+
+        match range {
+            Ok(Some((start, end))) => {
+                response
+                    .status(Status::PartialContent)
+                    .header(Header::new(
+                        "Content-Range",
+                        format!("bytes {}-{}/{}", start, end, self.len),
+                    ))
+                    .sized_body(
+                        Some((end - start + 1) as usize),
+                        FileRange {
+                            file: self.file,
+                            start,
+                            end: end + 1,
+                            position: start,
+                            seek: SeekState::Pending,
+                        },
+                    );
+            }
+            Ok(None) => {
+                response.sized_body(Some(self.len as usize), self.file);
+            }
+            Err(()) => {
+                response
+                    .status(Status::RangeNotSatisfiable)
+                    .header(Header::new(
+                        "Content-Range",
+                        format!("bytes */{}", self.len),
+                    ));
+            }
+        }
+
+        response.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the forms of ranges that browsers and podcast apps send.
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-", 1000), Ok(Some((0, 999))));
+        assert_eq!(parse_range("bytes=100-199", 1000), Ok(Some((100, 199))));
+        assert_eq!(parse_range("bytes=900-5000", 1000), Ok(Some((900, 999))));
+        assert_eq!(parse_range("bytes=-100", 1000), Ok(Some((900, 999))));
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), Ok(None));
+        assert_eq!(parse_range("items=0-1", 1000), Ok(None));
+        assert_eq!(parse_range("bytes=1000-", 1000), Err(()));
+        assert_eq!(parse_range("bytes=5-1", 1000), Err(()));
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::defaults::{POLL_VOTE_WINDOW_SECS, POLL_VOTES_PER_WINDOW};
+
+/// Limits how often an IP address may do something, e.g. vote in polls,
+/// to at most `max_hits` times within `window`.
+///
+/// This is only kept in memory, so it is emptied when the server restarts.
+#[derive(Debug)]
+pub struct RateLimit {
+    max_hits: usize,
+    window: Duration,
+    /// IP address -> when it did the thing, oldest first.
+    hits: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl RateLimit {
+    pub fn new(max_hits: usize, window: Duration) -> Self {
+        Self {
+            max_hits,
+            window,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The limit for votes in polls.
+    pub fn for_poll_votes() -> Self {
+        Self::new(
+            POLL_VOTES_PER_WINDOW,
+            Duration::from_secs(POLL_VOTE_WINDOW_SECS),
+        )
+    }
+
+    /// Records that `ip` does the thing now, unless it has already done it `max_hits` times within the window.
+    /// Returns whether it was allowed.
+    pub fn hit(&self, ip: IpAddr) -> bool {
+        let mut hits = self.hits.lock().unwrap_or_else(|err| err.into_inner());
+        hits.retain(|_, ip_hits| {
+            while ip_hits
+                .front()
+                .is_some_and(|hit| hit.elapsed() >= self.window)
+            {
+                ip_hits.pop_front();
+            }
+            !ip_hits.is_empty()
+        });
+
+        let ip_hits = hits.entry(ip).or_default();
+        if ip_hits.len() >= self.max_hits {
+            return false;
+        }
+        ip_hits.push_back(Instant::now());
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that an IP address is stopped after `max_hits`, without affecting other addresses.
+    #[test]
+    fn test_rate_limit() {
+        let rate_limit = RateLimit::new(2, Duration::from_secs(60));
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let other_ip: IpAddr = "192.0.2.2".parse().unwrap();
+
+        assert!(rate_limit.hit(ip));
+        assert!(rate_limit.hit(ip));
+        assert!(!rate_limit.hit(ip));
+        assert!(rate_limit.hit(other_ip));
+    }
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use tokio::time;
+
+use crate::{
+    database::{
+        DatabaseHandler,
+        models::{article::Text, trash::TrashedItem},
+    },
+    defaults::SCHEDULER_INTERVAL_SECS,
+};
+
+/// Runs the background tasks of the site, such as publishing scheduled texts and emptying the trash.
+/// When it starts, it first fills in the body text of texts stored before it was rendered, see `Text::fill_body_text`.
+/// This never returns, so it should be spawned as a separate task.
+pub async fn run(db: DatabaseHandler) {
+    match Text::fill_body_text(&db).await {
+        Ok(0) => (),
+        Ok(filled) => println!(
+            "Scheduler: rendered the body text of {} texts for search",
+            filled
+        ),
+        Err(err) => println!(
+            "Scheduler: failed to render the body text of texts:\n{}",
+            err
+        ),
+    }
+
+    let mut interval = time::interval(Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        match Text::publish_due(&db).await {
+            Ok(published_ids) if !published_ids.is_empty() => {
+                println!("Scheduler: published texts {:?}", published_ids)
+            }
+            Ok(_) => (),
+            Err(err) => println!("Scheduler: failed to publish scheduled texts:\n{}", err),
+        }
+
+        match TrashedItem::purge_expired(&db).await {
+            Ok(0) => (),
+            Ok(purged) => println!("Scheduler: purged {} trashed items", purged),
+            Err(err) => println!("Scheduler: failed to purge the trash:\n{}", err),
+        }
+    }
+}
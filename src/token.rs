@@ -33,6 +33,17 @@ pub fn get_decoding_key() -> DecodingKey {
     DecodingKey::from_secret(&read_token_secret())
 }
 
+/// `PreviewClaims` is the payload for the JWTs in preview links, see `PreviewLink`.
+/// These never grant access to anything but reading one text.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreviewClaims {
+    pub exp: usize,
+    /// Id of the text that may be previewed.
+    pub sub: i32,
+    /// Id of the `PreviewLink`, so that the link can be revoked.
+    pub jti: i32,
+}
+
 /// `Claims` is basically the payload for the JWTs.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -31,3 +31,32 @@ shareButtons.forEach(shareButton => {
         })
     }
 })
+
+// Audio files are sent on their own instead of in a form, since they may be far larger than forms are allowed to be
+const audioUploadForm = document.querySelector("form.audio-upload")
+
+audioUploadForm?.addEventListener("submit", event => {
+    event.preventDefault()
+    const audio = audioUploadForm.querySelector("#audio").files[0]
+    const url = new URL(audioUploadForm.action)
+    url.searchParams.set("title", audioUploadForm.querySelector("#title").value)
+    url.searchParams.set("description", audioUploadForm.querySelector("#description").value)
+
+    audioUploadForm.querySelector("button[type=submit]").disabled = true
+    fetch(url, {
+        method: "post",
+        headers: {
+            "Content-Type": audio.type
+        },
+        body: audio
+    }).then(async response => {
+        if (response.ok) {
+            window.location.href = response.url
+        } else {
+            document.documentElement.innerHTML = await response.text()
+        }
+    }).catch(error => {
+        console.error(error)
+        audioUploadForm.querySelector("button[type=submit]").disabled = false
+    })
+})
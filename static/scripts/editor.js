@@ -3,10 +3,10 @@
  * @param {HTMLElement} block The block to rig.
  */
 function rigBlock(block) {
-    const moveUpButton = block.querySelector("[icon=\"keyboard_arrow_up\"]")
-    const moveDownButton = block.querySelector("[icon=\"keyboard_arrow_down\"]")
-    const deleteButton = block.querySelector("[icon=\"delete\"]")
-    const addButton = block.querySelector("[icon=\"add\"]")
+    const moveUpButton = block.querySelector(":scope > .block-actions [icon=\"keyboard_arrow_up\"]")
+    const moveDownButton = block.querySelector(":scope > .block-actions [icon=\"keyboard_arrow_down\"]")
+    const deleteButton = block.querySelector(":scope > .block-actions [icon=\"delete\"]")
+    const addButton = block.querySelector(":scope > .block-actions [icon=\"add\"]")
 
     deleteButton.addEventListener("click", () => block.remove())
     moveUpButton.addEventListener("click", () => {
@@ -27,56 +27,66 @@ function rigBlock(block) {
 
 }
 
+const textForm = document.querySelector("#text-form")
+
 /**
- * @constant {Object} BLOCK_TYPE_TO_FIELDS
- * Maps block types to field names for that block type.
+ * Gets the value of a block field, see `FieldInput`.
+ * @param {HTMLElement} field The element with the `data-field` attribute.
+ * @returns {string|Object[]} The value.
  */
-const BLOCK_TYPE_TO_FIELDS = Object.freeze({
-    "Paragraph": ["body_text"],
-    "Image": ["id", "caption"],
-    "Quote": ["quote", "citation"],
-    "Heading": ["heading"],
-    "RawHtml": ["html"],
-    "YouTube": ["video_link", "caption"],
-    "TextBox": ["text", "color"],
-})
+function fieldValue(field) {
+    if (field.getAttribute("data-field-kind") === "ImageList") {
+        return Array.from(field.querySelectorAll("ol > .image-list-item")).map(item => {
+            let itemData = {}
+            item.querySelectorAll("[data-item-field]").forEach(itemField => {
+                itemData[itemField.getAttribute("data-item-field")] = itemField.value
+            })
+            return itemData
+        })
+    }
 
-const textForm = document.querySelector("#text-form")
-textForm.addEventListener(
-    "submit",
-    event => {
-        event.preventDefault()
-        let formData = new FormData(textForm)
+    return field.value
+}
 
-        const blockDivs = Array.from(textForm.querySelectorAll(".block-editor > .block"))
+/**
+ * Collects everything in the editor form, including the blocks, in the shape the API expects.
+ * @returns {Object} The text data.
+ */
+function collectTextData() {
+    let formData = new FormData(textForm)
 
-        const blocks = blockDivs.map(blockDiv => {
-            const blockType = blockDiv.getAttribute("data-block-type")
-            if (!BLOCK_TYPE_TO_FIELDS[blockType]) {
-                alert(`Unknown block type: ${blockType}`)
-                throw new Error(`Unknown block type: ${blockType}`)
-            }
+    const blockDivs = Array.from(textForm.querySelectorAll(".block-editor > .block"))
 
-            let blockData = {}
-            BLOCK_TYPE_TO_FIELDS[blockType].forEach(fieldName => {
-                const fieldData = blockDiv.querySelector(`.${fieldName}`).value
-                blockData[fieldName] = fieldData
-            })
+    const blocks = blockDivs.map(blockDiv => {
+        let blockData = {}
+        blockDiv.querySelectorAll("[data-field]").forEach(field => {
+            blockData[field.getAttribute("data-field")] = fieldValue(field)
+        })
 
-            blockData.type = blockType
+        blockData.type = blockDiv.getAttribute("data-block-type")
 
-            return blockData
-        })
+        return blockData
+    })
 
-        let textData = {}
+    let textData = {}
 
-        for (const key of formData.keys()) {
-            textData[key] = formData.get(key)
-        }
+    for (const key of formData.keys()) {
+        textData[key] = formData.get(key)
+    }
 
-        textData["text-id"] = Number(textData["text-id"])
+    textData["text-id"] = Number(textData["text-id"])
 
-        textData.blocks = blocks
+    textData.blocks = blocks
+
+    return textData
+}
+
+textForm.addEventListener(
+    "submit",
+    event => {
+        event.preventDefault()
+        clearBlockErrors()
+        const textData = collectTextData()
 
         fetch(
             textForm.action,
@@ -88,9 +98,19 @@ textForm.addEventListener(
                 body: JSON.stringify(textData)
             }
         ).then(
-            result => result.json()
+            result => result.json().then(response => ({ status: result.status, response }))
         ).then(
-            response => window.location.replace(response.redirect)
+            ({ status, response }) => {
+                if (status === 409) {
+                    handleConflict(response)
+                } else if (status === 422) {
+                    showBlockErrors(response.errors)
+                } else if (response.redirect) {
+                    window.location.replace(response.redirect)
+                } else {
+                    throw new Error(response.err_string)
+                }
+            }
         ).catch(
             error => {
                 alert("Encountered an error! Check the console for more information.")
@@ -99,58 +119,141 @@ textForm.addEventListener(
         )
     }
 )
-const BLOCK_TEMPLATE = `<div class="block" data-block-type="__BLOCK_TYPE__">
-    __BLOCK_DATA__
-    <div class="block-actions">
-        <button class="btn icon-only" type="button" icon="keyboard_arrow_up" title="Flytta blocket uppåt"></button>
-        <button class="btn icon-only" type="button" icon="keyboard_arrow_down" title="Flytta blocket nedåt"></button>
-        <div class="sep"></div>
-        <button class="btn dangerous icon-only" type="button" icon="delete" title="Radera blocket"></button>
-        <button class="btn icon-only" type="button" icon="add" title="Lägg till block under"></button>
-    </div>
-</div>`
-
-const BLOCK_TEMPLATES = Object.freeze({
-    "Paragraph": `
-        <textarea class="body_text" placeholder="Skriv brödtext här..."></textarea>
-    `,
-    "Image": `
-        <p>Bildens ID:</p>
-        <input class="id" placeholder="Skriv bildens id här">
-        <p>Bildtext:</p>
-        <input class="caption" placeholder="Skriv bildtext här...">
-    `,
-    "Quote": `
-        <label for="quote">Citat:</label>
-        <input class="quote" placeholder="Skriv citat här...">
-        <label for="citation">Referens:</label>
-        <input class="citation" placeholder="Skriv vem som sa/skrev det här...">
-    `,
-    "Heading": `
-        <input class="heading" placeholder="Skriv rubrik här...">
-    `,
-    "RawHtml": `
-        <textarea class="html" placeholder="Bädda in din HTML här."></textarea>
-    `,
-    "YouTube": `
-        <p>Länk till YouTube-videon:</p>
-        <input class="video_link" placeholder="Skriv YouTube-länken här">
-        <p>Bildtext:</p>
-        <input class="caption" placeholder="Skriv bildtext här...">
-    `,
-    "TextBox": `
-        <p>Text:</p>
-        <textarea class="text"></textarea>
-        <p>Färg:</p>
-        <select class="color">
-            <option value="Grey">Grå</option>
-            <option value="Green">Grön</option>
-            <option value="Yellow">Gul</option>
-            <option value="Red">Röd</option>
-            <option value="Blue">Blå</option>
-        </select>
-    `,
-})
+
+/**
+ * Handles a `409 Conflict`, i.e. someone else has saved since the editor was loaded.
+ * The user can either overwrite their changes, or keep editing and e.g. copy their changes elsewhere.
+ * @param {{err_string: string, current: {updated_at: string}}} conflict The response from the server.
+ */
+function handleConflict(conflict) {
+    const savedAt = new Date(conflict.current.updated_at).toLocaleString("sv-SE")
+    const overwrite = confirm(
+        `Någon annan har sparat en ny version (${savedAt}) sedan du öppnade editorn.\n\n`
+        + "Tryck OK för att skriva över deras ändringar med dina, "
+        + "eller Avbryt för att fortsätta redigera utan att spara."
+    )
+
+    if (overwrite) {
+        textForm.querySelector("#updated-at").value = conflict.current.updated_at
+        textForm.requestSubmit()
+    }
+}
+
+/**
+ * Marks the blocks and fields the server found invalid, and scrolls to the first one.
+ * @param {{index: number, field: string, message: string}[]} errors The errors from the server.
+ */
+function showBlockErrors(errors) {
+    const blockDivs = textForm.querySelectorAll(".block-editor > .block")
+
+    errors.forEach(error => {
+        const block = blockDivs[error.index]
+        if (!block) {
+            return
+        }
+        block.classList.add("invalid")
+
+        const message = document.createElement("p")
+        message.classList.add("block-error")
+        message.textContent = error.message
+
+        const field = block.querySelector(`[data-field="${error.field}"]`)
+        if (field) {
+            field.classList.add("invalid")
+            field.after(message)
+        } else {
+            block.prepend(message)
+        }
+    })
+
+    const firstInvalid = textForm.querySelector(".block.invalid")
+    if (firstInvalid) {
+        firstInvalid.scrollIntoView({ behavior: "smooth", block: "center" })
+    }
+}
+
+/**
+ * Removes everything `showBlockErrors` added.
+ */
+function clearBlockErrors() {
+    textForm.querySelectorAll(".block-error").forEach(message => message.remove())
+    textForm.querySelectorAll(".invalid").forEach(element => element.classList.remove("invalid"))
+}
+
+/**
+ * Shows the warnings the server has about the blocks, e.g. references to texts that are not published.
+ * Unlike errors, warnings don't stop the text from being saved.
+ */
+function updateBlockWarnings() {
+    fetch("/api/block/warnings", {
+        method: "post",
+        headers: {
+            "Content-Type": "application/json"
+        },
+        body: JSON.stringify(collectTextData().blocks)
+    }).then(
+        result => result.ok ? result.json() : []
+    ).then(warnings => {
+        textForm.querySelectorAll(".block-warning").forEach(message => message.remove())
+        const blockDivs = textForm.querySelectorAll(".block-editor > .block")
+
+        warnings.forEach(warning => {
+            const block = blockDivs[warning.index]
+            if (!block) {
+                return
+            }
+
+            const message = document.createElement("p")
+            message.classList.add("block-warning")
+            message.textContent = warning.message
+
+            const field = block.querySelector(`[data-field="${warning.field}"]`)
+            if (field) {
+                field.after(message)
+            } else {
+                block.prepend(message)
+            }
+        })
+    }).catch(error => console.error(error))
+}
+
+updateBlockWarnings()
+textForm.addEventListener("change", updateBlockWarnings)
+// Adding, moving and removing blocks changes which block a warning belongs to
+new MutationObserver(updateBlockWarnings).observe(textForm.querySelector(".block-editor"), { childList: true })
+
+// Autosave a working copy of texts (not pages) regularly, so that nothing is lost if the browser crashes
+const AUTOSAVE_INTERVAL_MS = 30000
+// Don't overwrite an earlier working copy that has not been recovered or discarded yet
+const hasPendingAutosave = document.querySelector("#autosave-notice form") !== null
+if (["/api/text/save", "/api/text/edit"].includes(new URL(textForm.action).pathname)) {
+    let hasUnsavedChanges = false
+    textForm.addEventListener("input", () => hasUnsavedChanges = true)
+    // Moving, adding and removing blocks does not fire `input`,
+    // but showing warnings and errors is not a change to the text
+    const isMessage = node => node.classList?.contains("block-warning") || node.classList?.contains("block-error")
+    new MutationObserver(mutations => {
+        if (mutations.some(mutation => ![...mutation.addedNodes, ...mutation.removedNodes].every(isMessage))) {
+            hasUnsavedChanges = true
+        }
+    })
+        .observe(textForm.querySelector(".block-editor"), { childList: true, subtree: true })
+
+    setInterval(() => {
+        if (!hasUnsavedChanges || hasPendingAutosave) {
+            return
+        }
+        hasUnsavedChanges = false
+
+        fetch("/api/text/autosave", {
+            method: "post",
+            headers: {
+                "Content-Type": "application/json"
+            },
+            body: JSON.stringify(collectTextData())
+        }).catch(error => console.error(error))
+    }, AUTOSAVE_INTERVAL_MS)
+}
 
 const addBlockButton = document.querySelector("button.add-block")
 const addBlockDialog = document.querySelector("dialog.add-block")
@@ -164,14 +267,66 @@ closeButton.addEventListener("click", event => {
 addBlockDialog.addEventListener("submit", event => {
     event.preventDefault()
     const blockType = addBlockDialog.querySelector("select[name=block-type]").value
-    const blockTemplate = BLOCK_TEMPLATES[blockType]
+    const blockTemplate = document.querySelector(`template.block-template[data-block-type="${blockType}"]`)
 
-    const block = BLOCK_TEMPLATE.replace("__BLOCK_TYPE__", blockType).replace("__BLOCK_DATA__", blockTemplate)
-    blockEditor.insertAdjacentHTML("beforeend", block)
-    rigBlock(blockEditor.lastChild)
+    blockEditor.append(blockTemplate.content.cloneNode(true))
+    rigBlock(blockEditor.lastElementChild)
     addBlockDialog.close()
 })
 
+/**
+ * Adds a block to the end of the editor, filled in with `block` as the API sends it.
+ * @param {Object} block The block, with its kind in `type` and its fields by name.
+ */
+function appendBlock(block) {
+    const blockTemplate = document.querySelector(`template.block-template[data-block-type="${block.type}"]`)
+    if (!blockTemplate) {
+        return
+    }
+
+    blockEditor.append(blockTemplate.content.cloneNode(true))
+    const blockDiv = blockEditor.lastElementChild
+    blockDiv.querySelectorAll("[data-field]").forEach(field => {
+        const value = block[field.getAttribute("data-field")]
+        if (typeof value === "string") {
+            field.value = value
+        }
+    })
+    rigBlock(blockDiv)
+}
+
+const importMarkdownInput = document.querySelector("input.import-markdown")
+document.querySelector("button.import-markdown").addEventListener("click", () => importMarkdownInput.click())
+importMarkdownInput.addEventListener("change", () => {
+    const file = importMarkdownInput.files[0]
+    if (!file) {
+        return
+    }
+
+    file.text().then(markdown => fetch("/api/block/import-markdown", {
+        method: "post",
+        headers: {
+            "Content-Type": "text/markdown"
+        },
+        body: markdown
+    })).then(result => {
+        if (!result.ok) {
+            throw new Error(`Could not import the file: ${result.status}`)
+        }
+        return result.json()
+    }).then(blocks => {
+        // Replace the empty paragraph that new texts start with
+        const existingBlocks = blockEditor.querySelectorAll(":scope > .block")
+        if (existingBlocks.length === 1 && collectTextData().blocks.every(block => block.body_text === "")) {
+            existingBlocks[0].remove()
+        }
+        blocks.forEach(appendBlock)
+    }).catch(error => {
+        console.error(error)
+        alert("Det gick inte att importera filen.")
+    }).finally(() => importMarkdownInput.value = "")
+})
+
 // Automatically rescale textareas to fit the text
 const textAreas = document.querySelectorAll("textarea")
 window.onload = () => textAreas.forEach(textArea => {
@@ -183,3 +338,138 @@ window.onload = () => textAreas.forEach(textArea => {
 
 const blocks = document.querySelectorAll(".block")
 blocks.forEach(rigBlock)
+
+// Highlight the block a review comment refers to
+document.querySelectorAll("button.block-reference").forEach(button => {
+    button.addEventListener("click", () => {
+        const block = blockEditor.children[Number(button.getAttribute("data-block-index"))]
+        if (!block) {
+            return
+        }
+        blockEditor.querySelectorAll(".block.highlighted").forEach(other => other.classList.remove("highlighted"))
+        block.classList.add("highlighted")
+        block.scrollIntoView({ behavior: "smooth", block: "center" })
+    })
+})
+
+// Show who else has the text open, as long as the editor is open
+const PRESENCE_PING_INTERVAL_MS = 20000
+const otherEditors = document.querySelector("#other-editors")
+const textIdInput = textForm.querySelector("#text-id")
+if (otherEditors && textIdInput) {
+    const presenceData = () => new URLSearchParams({ "text-id": textIdInput.value })
+
+    setInterval(() => {
+        fetch("/api/text/presence", { method: "post", body: presenceData() })
+            .then(result => result.json())
+            .then(names => {
+                const list = otherEditors.querySelector("ul")
+                list.replaceChildren(...names.map(name => {
+                    const item = document.createElement("li")
+                    item.textContent = name
+                    return item
+                }))
+                otherEditors.hidden = names.length === 0
+            })
+            .catch(error => console.error(error))
+    }, PRESENCE_PING_INTERVAL_MS)
+
+    window.addEventListener("pagehide", () => navigator.sendBeacon("/api/text/presence/leave", presenceData()))
+}
+
+// Image lists, e.g. in galleries: moving and removing images, and picking new ones from the image gallery
+const pickImagesDialog = document.querySelector("dialog.pick-images")
+const imageChoices = pickImagesDialog.querySelector(".image-choices")
+let pickImagesTarget = null
+let pickedImages = []
+
+/**
+ * Adds an image to an image list.
+ * @param {HTMLElement} imageList The element with `data-field-kind="ImageList"`.
+ * @param {string} id The id of the image.
+ */
+function addToImageList(imageList, id) {
+    const item = imageList.querySelector("template").content.cloneNode(true)
+    item.querySelector("img").src = `/dynamic-data/images/s/${id}.webp`
+    item.querySelector("[data-item-field=id]").value = id
+    imageList.querySelector("ol").append(item)
+}
+
+/**
+ * Shows the images matching `query` in the image picker.
+ * @param {string} query What to search for; empty shows all images.
+ */
+function loadImageChoices(query) {
+    fetch(`/api/image/search?q=${encodeURIComponent(query)}`)
+        .then(result => result.json())
+        .then(images => {
+            imageChoices.replaceChildren(...images.map(image => {
+                const choice = document.createElement("label")
+                choice.classList.add("image-choice")
+
+                const checkbox = document.createElement("input")
+                checkbox.type = "checkbox"
+                checkbox.value = image.id
+                checkbox.checked = pickedImages.includes(image.id)
+                checkbox.addEventListener("change", () => {
+                    pickedImages = pickedImages.filter(id => id !== image.id)
+                    if (checkbox.checked) {
+                        pickedImages.push(image.id)
+                    }
+                })
+
+                const thumbnail = document.createElement("img")
+                thumbnail.src = `/dynamic-data/images/s/${image.id}.webp`
+                // Search results highlight the matches with `<mark>`
+                thumbnail.alt = (image.description || "").replace(/<\/?mark>/g, "")
+                thumbnail.title = thumbnail.alt
+
+                choice.append(checkbox, thumbnail)
+                return choice
+            }))
+        })
+        .catch(error => console.error(error))
+}
+
+blockEditor.addEventListener("click", event => {
+    const button = event.target.closest("button[data-action]")
+    if (!button) {
+        return
+    }
+
+    const item = button.closest(".image-list-item")
+    switch (button.getAttribute("data-action")) {
+        case "move-up":
+            if (item.previousElementSibling) {
+                item.parentElement.insertBefore(item, item.previousElementSibling)
+            }
+            break
+        case "move-down":
+            if (item.nextElementSibling) {
+                item.parentElement.insertBefore(item.nextElementSibling, item)
+            }
+            break
+        case "remove":
+            item.remove()
+            break
+        case "pick-images":
+            pickImagesTarget = button.closest("[data-field-kind=ImageList]")
+            pickedImages = []
+            pickImagesDialog.querySelector("input[name=q]").value = ""
+            loadImageChoices("")
+            pickImagesDialog.showModal()
+            break
+    }
+})
+
+let imageSearchTimeout = null
+pickImagesDialog.querySelector("input[name=q]").addEventListener("input", event => {
+    clearTimeout(imageSearchTimeout)
+    imageSearchTimeout = setTimeout(() => loadImageChoices(event.target.value), 300)
+})
+pickImagesDialog.querySelector("button.close-dialog").addEventListener("click", () => pickImagesDialog.close())
+pickImagesDialog.addEventListener("submit", event => {
+    event.preventDefault()
+    pickedImages.forEach(id => addToImageList(pickImagesTarget, id))
+    pickImagesDialog.close()
+})
@@ -0,0 +1,9 @@
+// Embedded content is only loaded from the other site when the reader asks for it,
+// by replacing the notice with the iframe kept in its `<template>`.
+document.querySelectorAll(".embed > .embed-consent").forEach(consent => {
+    consent.querySelector("button").addEventListener("click", () => {
+        const iframe = consent.querySelector("template").content.firstElementChild.cloneNode(true)
+        consent.replaceWith(iframe)
+        iframe.focus()
+    })
+})
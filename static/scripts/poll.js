@@ -0,0 +1,82 @@
+/**
+ * Replaces the form of a poll with its results, in the same markup as `Poll::render_results`.
+ * @param {HTMLElement} poll The `.poll` section.
+ * @param {{options: string[], votes: number[]}} status The status of the poll from the server.
+ */
+function showResults(poll, status) {
+    const form = poll.querySelector("form")
+    const total = status.votes.reduce((sum, votes) => sum + votes, 0)
+
+    const question = document.createElement("p")
+    question.classList.add("question")
+    question.textContent = form.querySelector("legend").textContent
+
+    const results = document.createElement("ol")
+    results.classList.add("poll-results")
+    status.options.forEach((option, index) => {
+        const votes = status.votes[index] ?? 0
+        const percent = total > 0 ? Math.floor(votes * 100 / total) : 0
+
+        const result = document.createElement("li")
+        const optionSpan = document.createElement("span")
+        optionSpan.classList.add("option")
+        optionSpan.textContent = option
+        const shareSpan = document.createElement("span")
+        shareSpan.classList.add("share")
+        shareSpan.textContent = `${percent} % (${votes} röster)`
+        const progress = document.createElement("progress")
+        progress.max = 100
+        progress.value = percent
+
+        result.append(optionSpan, " ", shareSpan, progress)
+        results.append(result)
+    })
+
+    const totalParagraph = document.createElement("p")
+    totalParagraph.classList.add("poll-total")
+    totalParagraph.textContent = `${total} röster totalt.`
+
+    form.replaceWith(question, results, totalParagraph)
+}
+
+function fetchStatus(id) {
+    return fetch(`/api/poll/${id}`).then(result => result.json())
+}
+
+// Readers who have voted see the results instead of the form, and vote without leaving the page.
+// Closed polls are rendered with their results by the server.
+document.querySelectorAll(".poll[data-poll-id]").forEach(poll => {
+    const id = poll.getAttribute("data-poll-id")
+    const form = poll.querySelector("form")
+    if (!form) {
+        return
+    }
+
+    fetchStatus(id).then(status => {
+        if (status.votes) {
+            showResults(poll, status)
+        }
+    }).catch(error => console.error(error))
+
+    form.addEventListener("submit", event => {
+        event.preventDefault()
+
+        fetch(form.action, {
+            method: "post",
+            body: new URLSearchParams(new FormData(form)),
+            redirect: "manual"
+        }).then(result => {
+            // 409 Conflict means that this browser has already voted
+            if (result.type === "opaqueredirect" || result.status === 409) {
+                return fetchStatus(id).then(status => showResults(poll, status))
+            } else if (result.status === 429) {
+                alert("Det har kommit för många röster från din anslutning. Försök igen senare.")
+            } else {
+                throw new Error(`Voting failed with status ${result.status}`)
+            }
+        }).catch(error => {
+            alert("Din röst kunde inte räknas. Försök igen senare.")
+            console.error(error)
+        })
+    })
+})